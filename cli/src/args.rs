@@ -0,0 +1,79 @@
+use crate::files::expand_files;
+use common::level::TileArea;
+
+pub fn parse_area(text: &str) -> Result<TileArea, String> {
+    let values = text
+        .split(',')
+        .map(parse_number)
+        .collect::<Result<Vec<_>, _>>()?;
+    match values[..] {
+        [x0, y0, x1, y1] if x0 <= x1 && y0 <= y1 => Ok(((x0, y0), (x1, y1))),
+        _ => Err(format!("invalid area {}, expected x0,y0,x1,y1", text)),
+    }
+}
+
+pub fn parse_number(text: &str) -> Result<u32, String> {
+    text.parse().map_err(|_| format!("invalid number {}", text))
+}
+
+// Options of the commands which only take --json and files, with patterns in the files expanded
+pub fn parse_json_and_files(args: &[String], usage: &str) -> Result<(bool, Vec<String>), String> {
+    let mut json = false;
+    let mut files = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--help" => return Err(usage.to_string()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => files.push(arg.clone()),
+        }
+    }
+    if files.is_empty() {
+        return Err(usage.to_string());
+    }
+    Ok((json, expand_files(&files)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_area, parse_json_and_files, parse_number};
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn areas_are_ordered_corners() {
+        assert_eq!(parse_area("1,2,3,4").unwrap(), ((1, 2), (3, 4)));
+        assert_eq!(parse_area("5,5,5,5").unwrap(), ((5, 5), (5, 5)));
+        assert!(parse_area("3,2,1,4").is_err());
+        assert!(parse_area("1,2,3").is_err());
+        assert_eq!(parse_area("1,2,x,4").err().unwrap(), "invalid number x");
+        assert!(parse_number("-1").is_err());
+    }
+
+    #[test]
+    fn json_option_and_files() {
+        let (json, files) = parse_json_and_files(&args("a.lev --json b.txt"), "usage").unwrap();
+        assert!(json);
+        assert_eq!(files, ["a.lev", "b.txt"]);
+        assert_eq!(
+            parse_json_and_files(&args("--jsn a.lev"), "usage")
+                .err()
+                .unwrap(),
+            "unknown option --jsn"
+        );
+        assert_eq!(
+            parse_json_and_files(&args("--json"), "usage")
+                .err()
+                .unwrap(),
+            "usage"
+        );
+        assert_eq!(
+            parse_json_and_files(&args("--help a.lev"), "usage")
+                .err()
+                .unwrap(),
+            "usage"
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use crate::files::{expand_files, is_text_level, read_level, write_level};
+use std::path::Path;
+
+const USAGE: &str = "usage: utk-lev convert [options] <files>...
+
+Converts .LEV files to the text level format and text levels (.txt) back to
+.LEV files. Shadow locks are read from and written to the .SHL file next to
+the .LEV file.
+
+options:
+  --output <file>          file to write when converting a single level,
+                           defaults to the level file name with a .txt or
+                           .LEV extension";
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let mut output = None;
+    let mut files = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--output" => output = Some(value()?.clone()),
+            "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => files.push(arg.clone()),
+        }
+    }
+    if files.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    let files = expand_files(&files)?;
+    if output.is_some() && files.len() > 1 {
+        return Err("--output can only be used with a single file".to_string());
+    }
+
+    for file in &files {
+        let level = read_level(file)?;
+        let output = output.clone().unwrap_or_else(|| {
+            let extension = if is_text_level(file) { "LEV" } else { "txt" };
+            Path::new(file)
+                .with_extension(extension)
+                .to_string_lossy()
+                .into_owned()
+        });
+        write_level(&output, &level)?;
+        println!("{}: wrote {}", file, output);
+    }
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use crate::files::expand_files;
+use crate::json::Json;
+use common::level_dump::{dump_level, get_problems, DumpField, DumpSection};
+use std::fs;
+
+const USAGE: &str = "usage: utk-lev dump [options] <files>...
+
+Decodes .LEV files field by field in the order Level::deserialize reads them,
+printing the offset, raw bytes and decoded value of each field. Problems are
+marked with ! where loading fails, runs out of data or loads a different value
+than the file has.
+
+options:
+  --problems               only print the fields with problems
+  --json                   print one JSON object per file instead of text";
+
+const BYTES_PER_LINE: usize = 12;
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let mut json = false;
+    let mut only_problems = false;
+    let mut files = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--problems" => only_problems = true,
+            "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => files.push(arg.clone()),
+        }
+    }
+    if files.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    for file in &expand_files(&files)? {
+        let data = fs::read(file).map_err(|e| format!("{}: {}", file, e))?;
+        let sections = dump_level(&data);
+        let problems = get_problems(&sections).count();
+        if json {
+            println!(
+                "{}",
+                dump_json(file, data.len(), &sections, problems, only_problems)
+            );
+            continue;
+        }
+        println!("{}: {} bytes, {} problems", file, data.len(), problems);
+        for section in &sections {
+            let fields = section
+                .fields
+                .iter()
+                .filter(|field| !only_problems || field.problem.is_some())
+                .collect::<Vec<_>>();
+            if fields.is_empty() {
+                continue;
+            }
+            println!("{}", section.name);
+            for field in fields {
+                print_field(field);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_field(field: &DumpField) {
+    let mut lines = field.bytes.chunks(BYTES_PER_LINE);
+    println!(
+        "  {:06x}  {:<width$}  {}: {}",
+        field.offset,
+        hex(lines.next().unwrap_or_default(), " "),
+        field.name,
+        field.value,
+        width = BYTES_PER_LINE * 3 - 1
+    );
+    for (line, bytes) in lines.enumerate() {
+        println!(
+            "  {:06x}  {}",
+            field.offset + (line + 1) * BYTES_PER_LINE,
+            hex(bytes, " ")
+        );
+    }
+    if let Some(problem) = &field.problem {
+        println!("  ! {}", problem);
+    }
+}
+
+fn hex(bytes: &[u8], separator: &str) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn dump_json(
+    file: &str,
+    length: usize,
+    sections: &[DumpSection],
+    problems: usize,
+    only_problems: bool,
+) -> Json {
+    let sections = sections
+        .iter()
+        .filter_map(|section| {
+            let fields = section
+                .fields
+                .iter()
+                .filter(|field| !only_problems || field.problem.is_some())
+                .map(|field| {
+                    let mut values = vec![
+                        ("offset", field.offset.into()),
+                        ("bytes", hex(&field.bytes, "").into()),
+                        ("name", field.name.as_str().into()),
+                        ("value", field.value.as_str().into()),
+                    ];
+                    if let Some(problem) = &field.problem {
+                        values.push(("problem", problem.as_str().into()));
+                    }
+                    Json::Object(values)
+                })
+                .collect::<Vec<_>>();
+            (!fields.is_empty()).then(|| {
+                Json::Object(vec![
+                    ("name", section.name.into()),
+                    ("fields", Json::Array(fields)),
+                ])
+            })
+        })
+        .collect();
+    Json::Object(vec![
+        ("file", file.into()),
+        ("length", length.into()),
+        ("problems", problems.into()),
+        ("sections", Json::Array(sections)),
+    ])
+}
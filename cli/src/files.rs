@@ -0,0 +1,195 @@
+use common::level::{Level, SHADOW_LOCK_EXTENSION};
+use common::level_text::{format_level, parse_level};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Levels are read and written in the text format of common::level_text when the file has a .txt
+// extension and as .LEV files otherwise
+pub fn is_text_level(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("txt"))
+}
+
+pub fn read_level(path: &str) -> Result<Level, String> {
+    if is_text_level(path) {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        return parse_level(&text).map_err(|e| format!("{}:{}: {}", path, e.line, e.message));
+    }
+    let data = fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut level =
+        Level::deserialize(&data).map_err(|e| format!("{}: invalid level file ({:?})", path, e))?;
+    let sidecar_path = find_sidecar(Path::new(path), SHADOW_LOCK_EXTENSION);
+    if let Ok(sidecar_data) = fs::read(&sidecar_path) {
+        level.deserialize_shadow_locks(&sidecar_data).map_err(|e| {
+            format!(
+                "{}: invalid shadow lock file ({:?})",
+                sidecar_path.display(),
+                e
+            )
+        })?;
+    }
+    Ok(level)
+}
+
+pub fn write_level(path: &str, level: &Level) -> Result<(), String> {
+    if is_text_level(path) {
+        return fs::write(path, format_level(level)).map_err(|e| format!("{}: {}", path, e));
+    }
+    fs::write(path, level.serialize()).map_err(|e| format!("{}: {}", path, e))?;
+    // A sidecar left from an earlier write would bring back locks which have been cleared
+    let sidecar_path = find_sidecar(Path::new(path), SHADOW_LOCK_EXTENSION);
+    let result = if level.shadow_locks.is_empty() {
+        match fs::remove_file(&sidecar_path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    } else {
+        fs::write(&sidecar_path, level.serialize_shadow_locks())
+    };
+    result.map_err(|e| format!("{}: {}", sidecar_path.display(), e))
+}
+
+// Path of the sidecar file of a level with the given extension. The editor saves sidecars with
+// upper case names, so an existing file is looked up ignoring case.
+pub fn find_sidecar(level_path: &Path, extension: &str) -> PathBuf {
+    let sidecar_path = level_path.with_extension(extension);
+    let Some(sidecar_name) = sidecar_path.file_name().and_then(|name| name.to_str()) else {
+        return sidecar_path;
+    };
+    let directory = level_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    fs::read_dir(directory)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name())
+        .find(|name| {
+            name.to_str()
+                .is_some_and(|name| name.eq_ignore_ascii_case(sidecar_name))
+        })
+        .map_or(sidecar_path.clone(), |name| {
+            sidecar_path.with_file_name(name)
+        })
+}
+
+// Expands * and ? in the file name part of the arguments, for shells which do not do it and for
+// quoted patterns in build scripts. Matching ignores case like the DOS file names of the levels.
+pub fn expand_files(patterns: &[String]) -> Result<Vec<String>, String> {
+    let mut files = Vec::new();
+    for pattern in patterns {
+        let path = Path::new(pattern);
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        if !name.contains(['*', '?']) {
+            files.push(pattern.clone());
+            continue;
+        }
+        let directory = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let mut matches = fs::read_dir(directory)
+            .map_err(|e| format!("{}: {}", directory.display(), e))?
+            .filter_map(Result::ok)
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|file_name| {
+                matches_pattern(
+                    name.to_ascii_uppercase().as_bytes(),
+                    file_name.to_ascii_uppercase().as_bytes(),
+                )
+            })
+            .map(|file_name| {
+                path.with_file_name(file_name)
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect::<Vec<_>>();
+        if matches.is_empty() {
+            return Err(format!("{}: no matching files", pattern));
+        }
+        matches.sort();
+        files.extend(matches);
+    }
+    Ok(files)
+}
+
+fn matches_pattern(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            matches_pattern(&pattern[1..], name)
+                || (!name.is_empty() && matches_pattern(pattern, &name[1..]))
+        }
+        (Some(b'?'), Some(_)) => matches_pattern(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => matches_pattern(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_files, find_sidecar, matches_pattern};
+    use std::fs;
+
+    #[test]
+    fn patterns_match_whole_names() {
+        for (pattern, name, matches) in [
+            ("*.LEV", "LEVEL1.LEV", true),
+            ("*.LEV", "LEVEL1.LEVX", false),
+            ("LEVEL?.LEV", "LEVEL1.LEV", true),
+            ("LEVEL?.LEV", "LEVEL10.LEV", false),
+            ("*", "", true),
+            ("?", "", false),
+            ("L*1*.LEV", "LEVEL10.LEV", true),
+            ("**.LEV", ".LEV", true),
+            ("LEVEL1.LEV", "LEVEL1.LEV", true),
+        ] {
+            assert_eq!(
+                matches_pattern(pattern.as_bytes(), name.as_bytes()),
+                matches,
+                "{} {}",
+                pattern,
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn patterns_expand_sorted_ignoring_case() {
+        let directory = std::env::temp_dir().join(format!("utk-lev-expand-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        for name in ["b.lev", "A.LEV", "c.txt"] {
+            fs::write(directory.join(name), []).unwrap();
+        }
+        let pattern = |name: &str| directory.join(name).to_string_lossy().into_owned();
+
+        let files = expand_files(&[pattern("*.Lev"), "plain.lev".to_string()]).unwrap();
+        assert_eq!(
+            files,
+            [pattern("A.LEV"), pattern("b.lev"), "plain.lev".to_string()]
+        );
+        assert_eq!(
+            expand_files(&[pattern("*.SHL")]).err().unwrap(),
+            format!("{}: no matching files", pattern("*.SHL"))
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn sidecar_is_found_ignoring_case() {
+        let directory =
+            std::env::temp_dir().join(format!("utk-lev-sidecar-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let level_path = directory.join("foo.lev");
+        assert_eq!(find_sidecar(&level_path, "SHL"), directory.join("foo.SHL"));
+        fs::write(directory.join("FOO.SHL"), []).unwrap();
+        assert_eq!(find_sidecar(&level_path, "SHL"), directory.join("FOO.SHL"));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,133 @@
+use common::context::{Context, Textures};
+use common::fn2::FN2;
+use common::font::Font;
+use common::graphics::Graphics;
+use common::level::{Level, TILE_SIZE};
+use common::load_level::LevelLister;
+use common::render::{create_crate_icons, create_light_texture, Color, Renderer};
+use common::shadow_rules::ShadowRules;
+use common::software_renderer::{SoftwareRenderer, SoftwareTexture};
+use common::types::{TextureType, Trigonometry};
+use common::util::get_number_of_tiles_in_texture;
+use png::{BitDepth, ColorType, Decoder, Encoder, Transformations};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+pub fn load_textures(
+    renderer: &mut SoftwareRenderer,
+    assets: &str,
+    fn2: &FN2,
+) -> Result<Textures<SoftwareTexture>, String> {
+    let asset_path = |name: &str| Path::new(assets).join(name).to_string_lossy().into_owned();
+    Ok(Textures {
+        floor: load_texture(renderer, &asset_path("FLOOR1.PNG"))?,
+        walls: load_texture(renderer, &asset_path("WALLS1.PNG"))?,
+        shadows: load_texture(renderer, &asset_path("SHADOWS_ALPHA.PNG"))?,
+        light: create_light_texture(renderer),
+        crates: load_texture(renderer, &asset_path("CRATES.PNG"))
+            .unwrap_or_else(|_| create_crate_icons(renderer, fn2)),
+    })
+}
+
+// Editor context for rendering without a window, with the textures, font and shadow rules read
+// from the assets directory like the desktop editor does
+pub fn create_context<L: LevelLister>(
+    renderer: &mut SoftwareRenderer,
+    assets: &str,
+    level: Level,
+    level_lister: L,
+    render_multiplier: u32,
+) -> Result<Context<L, SoftwareTexture>, String> {
+    let asset_path = |name: &str| Path::new(assets).join(name).to_string_lossy().into_owned();
+    let fn2 = FN2::parse(
+        &fs::read(asset_path("TETRIS.FN2"))
+            .map_err(|e| format!("{}: {}", asset_path("TETRIS.FN2"), e))?,
+    );
+    let textures = load_textures(renderer, assets, &fn2)?;
+    let shadow_rules = match fs::read_to_string(asset_path("SHADOW_RULES.TXT")) {
+        Ok(text) => ShadowRules::parse(&text)
+            .and_then(|rules| {
+                rules.check_shadows(get_number_of_tiles_in_texture(&textures.shadows))
+            })
+            .map_err(|e| {
+                format!(
+                    "{}: invalid shadow rules ({:?})",
+                    asset_path("SHADOW_RULES.TXT"),
+                    e
+                )
+            })?,
+        Err(_) => ShadowRules::default(),
+    };
+    Ok(Context {
+        graphics: Graphics::new(renderer.window_size(), render_multiplier),
+        font: Font::new(renderer, &fn2, render_multiplier),
+        fn2,
+        textures,
+        level,
+        level_lister,
+        selected_tile_id: 0,
+        texture_type_selected: TextureType::Floor,
+        texture_type_scrolled: TextureType::Floor,
+        mouse: (0, 0),
+        viewport: (0, 0),
+        level_save_name: String::new(),
+        saved_level_name: None,
+        unsaved_changes: false,
+        trigonometry: Trigonometry::new(),
+        automatic_shadows: true,
+        shadow_rules,
+    })
+}
+
+pub fn load_texture(renderer: &SoftwareRenderer, path: &str) -> Result<SoftwareTexture, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut decoder = Decoder::new(file);
+    decoder.set_transformations(Transformations::normalize_to_color8() | Transformations::ALPHA);
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| format!("{}: invalid PNG file ({})", path, e))?;
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut data)
+        .map_err(|e| format!("{}: invalid PNG file ({})", path, e))?;
+    let data = &data[..info.buffer_size()];
+    let rgba = match info.color_type {
+        ColorType::Rgba => data.to_vec(),
+        ColorType::GrayscaleAlpha => data
+            .chunks(2)
+            .flat_map(|c| [c[0], c[0], c[0], c[1]])
+            .collect(),
+        _ => return Err(format!("{}: unsupported PNG color type", path)),
+    };
+    Ok(renderer.create_texture_rgba(info.width, info.height, &rgba))
+}
+
+// Number of tiles in a tile texture, like get_number_of_tiles_in_texture without loading the pixels
+pub fn get_number_of_tiles_in_file(path: &str) -> Result<u32, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    let reader = Decoder::new(file)
+        .read_info()
+        .map_err(|e| format!("{}: invalid PNG file ({})", path, e))?;
+    let info = reader.info();
+    Ok(info.width / TILE_SIZE * info.height / TILE_SIZE)
+}
+
+pub fn save_png(renderer: &SoftwareRenderer, path: &str) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut encoder = Encoder::new(BufWriter::new(file), renderer.width(), renderer.height());
+    encoder.set_color(ColorType::Rgb);
+    encoder.set_depth(BitDepth::Eight);
+    let data = renderer
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = Color::from_u32(*pixel);
+            [color.r, color.g, color.b]
+        })
+        .collect::<Vec<_>>();
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&data))
+        .map_err(|e| format!("{}: {}", path, e))
+}
@@ -0,0 +1,201 @@
+use crate::args::parse_json_and_files;
+use crate::files::{is_text_level, read_level};
+use crate::json::Json;
+use common::level::{CrateSet, Level, StaticCrate, ENEMY_NAMES};
+use std::fs;
+
+const USAGE: &str = "usage: utk-lev info [options] <files>...
+
+Prints the size, file version, object counts and general level info of level
+files.
+
+options:
+  --json                   print one JSON object per file instead of text";
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let (json, files) = parse_json_and_files(args, USAGE)?;
+    let mut failed = 0;
+    for file in &files {
+        match read_level(file) {
+            Ok(level) => {
+                let version = read_version(file);
+                if json {
+                    println!("{}", info_json(file, version, &level));
+                } else {
+                    print_info(file, version, &level);
+                }
+            }
+            Err(message) => {
+                failed += 1;
+                if json {
+                    println!(
+                        "{}",
+                        Json::Object(vec![
+                            ("file", file.as_str().into()),
+                            ("error", message.into())
+                        ])
+                    );
+                } else {
+                    eprintln!("{}", message);
+                }
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(format!(
+            "{} of {} files could not be read",
+            failed,
+            files.len()
+        ));
+    }
+    Ok(())
+}
+
+// Version from the file header, text levels have none
+fn read_version(path: &str) -> Option<u32> {
+    if is_text_level(path) {
+        return None;
+    }
+    let data = fs::read(path).ok()?;
+    Some(u32::from_le_bytes(data.get(..4)?.try_into().ok()?))
+}
+
+fn count_static_crates(level: &Level, crate_variant: StaticCrate) -> usize {
+    level
+        .crates
+        .staticc
+        .values()
+        .filter(|crate_item| crate_item.crate_variant == crate_variant)
+        .count()
+}
+
+// Summed as u64, the amounts of a file can be anything up to u32::MAX
+fn count_random_crates(level: &Level) -> (u64, u64) {
+    let count = |set: &CrateSet| {
+        set.weapons
+            .iter()
+            .chain(&set.bullets)
+            .chain([&set.energy])
+            .map(|amount| *amount as u64)
+            .sum()
+    };
+    (
+        count(&level.crates.random.normal),
+        count(&level.crates.random.deathmatch),
+    )
+}
+
+fn print_info(file: &str, version: Option<u32>, level: &Level) {
+    let version = version.map_or("text".to_string(), |version| format!("version {}", version));
+    println!(
+        "{}: {}, {}x{} tiles",
+        file,
+        version,
+        level.tiles[0].len(),
+        level.tiles.len()
+    );
+    println!("  comment: {}", level.general_info.comment);
+    println!("  time limit: {} seconds", level.general_info.time_limit);
+    println!(
+        "  player starts: {},{} and {},{}",
+        level.p1_position.0, level.p1_position.1, level.p2_position.0, level.p2_position.1
+    );
+    println!("  spotlights: {}", level.spotlights.len());
+    println!("  steams: {}", level.steams.len());
+    println!(
+        "  static crates: {} normal, {} deathmatch",
+        count_static_crates(level, StaticCrate::Normal),
+        count_static_crates(level, StaticCrate::Deathmatch)
+    );
+    let (normal, deathmatch) = count_random_crates(level);
+    println!(
+        "  random crates: {} normal, {} deathmatch",
+        normal, deathmatch
+    );
+    let enemies = ENEMY_NAMES
+        .iter()
+        .zip(level.general_info.enemy_table)
+        .filter(|(_, amount)| *amount > 0)
+        .map(|(name, amount)| format!("{} {}", amount, name))
+        .collect::<Vec<_>>();
+    println!(
+        "  enemies: {}",
+        if enemies.is_empty() {
+            "none".to_string()
+        } else {
+            enemies.join(", ")
+        }
+    );
+}
+
+fn info_json(file: &str, version: Option<u32>, level: &Level) -> Json {
+    let (normal, deathmatch) = count_random_crates(level);
+    let mut fields = vec![("file", file.into())];
+    if let Some(version) = version {
+        fields.push(("version", version.into()));
+    }
+    fields.extend([
+        ("width", level.tiles[0].len().into()),
+        ("height", level.tiles.len().into()),
+        ("comment", level.general_info.comment.as_str().into()),
+        ("time_limit", level.general_info.time_limit.into()),
+        (
+            "player_starts",
+            vec![
+                vec![level.p1_position.0, level.p1_position.1],
+                vec![level.p2_position.0, level.p2_position.1],
+            ]
+            .into(),
+        ),
+        ("spotlights", level.spotlights.len().into()),
+        ("steams", level.steams.len().into()),
+        (
+            "static_crates",
+            Json::Object(vec![
+                (
+                    "normal",
+                    count_static_crates(level, StaticCrate::Normal).into(),
+                ),
+                (
+                    "deathmatch",
+                    count_static_crates(level, StaticCrate::Deathmatch).into(),
+                ),
+            ]),
+        ),
+        (
+            "random_crates",
+            Json::Object(vec![
+                ("normal", normal.into()),
+                ("deathmatch", deathmatch.into()),
+            ]),
+        ),
+        (
+            "enemies",
+            Json::Object(
+                ENEMY_NAMES
+                    .iter()
+                    .zip(level.general_info.enemy_table)
+                    .map(|(name, amount)| (*name, amount.into()))
+                    .collect(),
+            ),
+        ),
+    ]);
+    Json::Object(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::count_random_crates;
+    use common::level::Level;
+
+    #[test]
+    fn random_crates_do_not_overflow() {
+        let mut level = Level::get_default_level((16, 12));
+        assert_eq!(count_random_crates(&level), (3, 3));
+        let normal = &mut level.crates.random.normal;
+        normal.weapons = [u32::MAX; 11];
+        normal.bullets = [u32::MAX; 9];
+        normal.energy = u32::MAX;
+        assert_eq!(count_random_crates(&level), (21 * u32::MAX as u64, 3));
+    }
+}
@@ -0,0 +1,46 @@
+mod args;
+mod convert;
+mod dump;
+mod files;
+mod image;
+mod info;
+mod json;
+mod render;
+mod replace;
+mod replay;
+mod validate;
+
+use std::env;
+use std::process::exit;
+
+const USAGE: &str = "usage: utk-lev <command> [options] <files>...
+
+commands:
+  info       print the size, objects and general info of level files
+  validate   check level files for values the game does not support
+  convert    convert between .LEV files and the text level format
+  dump       decode .LEV files field by field and show where loading fails
+  replace    replace tiles in level files (see utk-lev replace --help)
+  render     render level files to PNG images (see utk-lev render --help)
+  replay     replay a recording of editor events (see utk-lev replay --help)
+
+Patterns with * and ? in the file names are expanded. info, validate and dump
+print one JSON object per file with --json.";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("info") => info::run(&args[1..]),
+        Some("validate") => validate::run(&args[1..]),
+        Some("convert") => convert::run(&args[1..]),
+        Some("dump") => dump::run(&args[1..]),
+        Some("replace") => replace::run(&args[1..]),
+        Some("render") => render::run(&args[1..]),
+        Some("replay") => replay::run(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(message) = result {
+        eprintln!("{}", message);
+        exit(1);
+    }
+}
@@ -0,0 +1,221 @@
+use crate::args::{parse_area, parse_number};
+use crate::files::{expand_files, read_level};
+use crate::image::{create_context, save_png};
+use common::editor::render_level;
+use common::layers::{CrateFilter, Layers};
+use common::level::{TileArea, TILE_SIZE};
+use common::load_level::LevelLister;
+use common::render::Renderer;
+use common::software_renderer::SoftwareRenderer;
+use std::path::Path;
+
+const USAGE: &str = "usage: utk-lev render [options] <files>...
+
+Renders level files to PNG images. Only the tiles are drawn by default.
+
+options:
+  --output <file>          image to write when rendering a single level,
+                           defaults to the level file name with a .png
+                           extension
+  --area <x0,y0,x1,y1>     only render this inclusive tile area
+  --zoom <percent>         image scale in steps of 5 percent, 100 draws tiles as
+                           20x20 pixels
+  --objects                draw spotlights, steams, crates and player starts
+  --no-shadows             leave out the shadows
+  --lighting               darken the level and light it by the spotlights
+  --assets <dir>           directory with the tile textures and font
+                           (default: assets)";
+
+struct Options {
+    output: Option<String>,
+    area: Option<TileArea>,
+    zoom: u32,
+    objects: bool,
+    shadows: bool,
+    lighting: bool,
+    assets: String,
+    files: Vec<String>,
+}
+
+// The level is rendered without any saving or loading, so there is nothing to list
+struct NoLevelLister;
+
+impl LevelLister for NoLevelLister {
+    fn refresh(&mut self) {}
+
+    fn reset(&mut self) {}
+
+    fn len(&self) -> usize {
+        0
+    }
+
+    fn level_name(&self, _index: usize) -> &str {
+        unreachable!()
+    }
+
+    fn load_level(&self, _index: usize) -> Vec<u8> {
+        unreachable!()
+    }
+
+    fn load_sidecar(&self, _index: usize, _extension: &str) -> Option<Vec<u8>> {
+        unreachable!()
+    }
+}
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let options = parse_options(args)?;
+    for file in &options.files {
+        render_file(&options, file)?;
+    }
+    Ok(())
+}
+
+fn render_file(options: &Options, file: &str) -> Result<(), String> {
+    let level = read_level(file)?;
+    let level_size = (level.tiles[0].len() as u32, level.tiles.len() as u32);
+    let ((x0, y0), (x1, y1)) = options
+        .area
+        .unwrap_or(((0, 0), (level_size.0 - 1, level_size.1 - 1)));
+    if x1 >= level_size.0 || y1 >= level_size.1 {
+        return Err(format!(
+            "{}: area is outside of the {}x{} level",
+            file, level_size.0, level_size.1
+        ));
+    }
+
+    let render_size = TILE_SIZE * options.zoom / 100;
+    let (width, height) = ((x1 - x0 + 1) * render_size, (y1 - y0 + 1) * render_size);
+    let mut renderer = SoftwareRenderer::new(width, height);
+    let mut context = create_context(&mut renderer, &options.assets, level, NoLevelLister, 1)?;
+    context.graphics.zoom = options.zoom;
+    context.viewport = (x0 * TILE_SIZE, y0 * TILE_SIZE);
+    context.automatic_shadows = false;
+
+    let mut layers = Layers::default();
+    layers.shadows = options.shadows;
+    layers.lighting = options.lighting;
+    layers.spotlights = options.objects;
+    layers.steams = options.objects;
+    layers.player_labels = options.objects;
+    if !options.objects {
+        layers.crates = CrateFilter::None;
+    }
+    renderer.clear_screen();
+    render_level(&mut renderer, &context, &layers, 0, &[]);
+
+    let output = options.output.clone().unwrap_or_else(|| {
+        Path::new(file)
+            .with_extension("png")
+            .to_string_lossy()
+            .into_owned()
+    });
+    save_png(&renderer, &output)?;
+    println!("{}: wrote {}x{} image to {}", file, width, height, output);
+    Ok(())
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        output: None,
+        area: None,
+        zoom: 100,
+        objects: false,
+        shadows: true,
+        lighting: false,
+        assets: "assets".to_string(),
+        files: Vec::new(),
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--output" => options.output = Some(value()?.clone()),
+            "--area" => options.area = Some(parse_area(value()?)?),
+            "--zoom" => options.zoom = parse_number(value()?)?,
+            "--objects" => options.objects = true,
+            "--no-shadows" => options.shadows = false,
+            "--lighting" => options.lighting = true,
+            "--assets" => options.assets = value()?.clone(),
+            "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => options.files.push(arg.clone()),
+        }
+    }
+
+    // Tiles are positioned by the exact scale but drawn with the rounded down size, which leaves
+    // seams between them unless the tile size scales to whole pixels
+    if options.zoom < 5 || options.zoom > 1000 || !(TILE_SIZE * options.zoom).is_multiple_of(100) {
+        return Err(format!(
+            "invalid zoom {}, expected 5 to 1000 percent in steps of 5",
+            options.zoom
+        ));
+    }
+    if options.files.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    options.files = expand_files(&options.files)?;
+    if options.output.is_some() && options.files.len() > 1 {
+        return Err("--output can only be used with a single file".to_string());
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_options, render_file};
+    use crate::files::write_level;
+    use common::level::Level;
+    use png::Decoder;
+    use std::fs::{self, File};
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn zoom_must_scale_tiles_to_whole_pixels() {
+        assert!(parse_options(&args("--zoom 35 a.lev")).is_ok());
+        assert_eq!(
+            parse_options(&args("--zoom 33 a.lev")).err().unwrap(),
+            "invalid zoom 33, expected 5 to 1000 percent in steps of 5"
+        );
+    }
+
+    #[test]
+    fn odd_zoom_renders_whole_level_without_seams() {
+        let path = std::env::temp_dir().join(format!("utk-lev-render-{}", std::process::id()));
+        let (level_path, image_path) = (path.with_extension("txt"), path.with_extension("png"));
+        let (level_path, image_path) = (
+            level_path.to_string_lossy().into_owned(),
+            image_path.to_string_lossy().into_owned(),
+        );
+        write_level(&level_path, &Level::get_default_level((40, 20))).unwrap();
+        let options = parse_options(&args(&format!(
+            "--zoom 35 --assets ../assets --output {} {}",
+            image_path, level_path
+        )))
+        .unwrap();
+        render_file(&options, &level_path).unwrap();
+
+        let mut reader = Decoder::new(File::open(&image_path).unwrap())
+            .read_info()
+            .unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).unwrap();
+        fs::remove_file(&level_path).unwrap();
+        fs::remove_file(&image_path).unwrap();
+        // 7x7 pixel tiles
+        assert_eq!((info.width, info.height), (280, 140));
+        let bytes_per_pixel = info.line_size / info.width as usize;
+        let black_columns = (0..info.width as usize)
+            .filter(|x| {
+                (0..info.height as usize).all(|y| {
+                    let pixel = (y * info.width as usize + x) * bytes_per_pixel;
+                    data[pixel..pixel + 3] == [0, 0, 0]
+                })
+            })
+            .count();
+        assert_eq!(black_columns, 0);
+    }
+}
@@ -0,0 +1,241 @@
+use crate::args::{parse_area, parse_number};
+use crate::files::{expand_files, read_level, write_level};
+use crate::image::get_number_of_tiles_in_file;
+use common::level::{TileArea, TileMatch};
+use common::shadow_rules::ShadowRules;
+use common::types::TextureType;
+use std::fs;
+use std::path::Path;
+
+const USAGE: &str = "usage: utk-lev replace --from <tile> --to <tile> [options] <files>...
+
+Replaces every tile matching --from with the --to tile. Tiles are given as
+floor:<id> or wall:<id>.
+
+options:
+  --shadow <value>         only replace tiles which also have this shadow value
+  --area <x0,y0,x1,y1>     only replace tiles inside this inclusive tile area
+  --create-shadows         regenerate automatic shadows after replacing
+  --shadow-rules <file>    shadow rules used by --create-shadows, see
+                           assets/SHADOW_RULES.TXT
+  --dry-run                only print how many tiles would be replaced
+  --assets <dir>           directory with the tile textures which --to and the
+                           shadow rules must be in (default: assets)";
+
+struct Options {
+    from: TileMatch,
+    to: (TextureType, u32),
+    area: Option<TileArea>,
+    create_shadows: Option<ShadowRules>,
+    dry_run: bool,
+    files: Vec<String>,
+}
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let options = parse_options(args)?;
+    for file in &options.files {
+        let mut level = read_level(file)?;
+        if options.dry_run {
+            let count = level.find_tiles(&options.from, options.area).len();
+            println!("{}: {} tiles would be replaced", file, count);
+        } else {
+            let count = level.replace_tiles(&options.from, options.to, options.area);
+            if let Some(rules) = &options.create_shadows {
+                level.create_shadows(rules);
+            }
+            if count > 0 || options.create_shadows.is_some() {
+                write_level(file, &level)?;
+            }
+            println!("{}: {} tiles replaced", file, count);
+        }
+    }
+    Ok(())
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut from = None;
+    let mut to = None;
+    let mut shadow = None;
+    let mut area = None;
+    let mut create_shadows = false;
+    let mut shadow_rules = None;
+    let mut dry_run = false;
+    let mut assets = "assets".to_string();
+    let mut files = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--from" => from = Some(parse_tile(value()?)?),
+            "--to" => to = Some(parse_tile(value()?)?),
+            "--shadow" => shadow = Some(parse_number(value()?)?),
+            "--area" => area = Some(parse_area(value()?)?),
+            "--create-shadows" => create_shadows = true,
+            "--shadow-rules" => shadow_rules = Some(value()?.clone()),
+            "--dry-run" => dry_run = true,
+            "--assets" => assets = value()?.clone(),
+            "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => files.push(arg.clone()),
+        }
+    }
+
+    let (from, to) = match (from, to) {
+        (Some(from), Some(to)) if !files.is_empty() => (from, to),
+        _ => return Err(USAGE.to_string()),
+    };
+    let texture = match to.0 {
+        TextureType::Walls => "WALLS1.PNG",
+        _ => "FLOOR1.PNG",
+    };
+    let texture_path = Path::new(&assets).join(texture);
+    check_tile_id(
+        to,
+        get_number_of_tiles_in_file(&texture_path.to_string_lossy())?,
+    )?;
+    let create_shadows = if create_shadows {
+        let shadows_path = Path::new(&assets).join("SHADOWS_ALPHA.PNG");
+        let shadow_tiles = get_number_of_tiles_in_file(&shadows_path.to_string_lossy())?;
+        Some(read_shadow_rules(shadow_rules.as_deref(), shadow_tiles)?)
+    } else {
+        None
+    };
+    Ok(Options {
+        from: TileMatch {
+            texture_type: from.0,
+            id: from.1,
+            shadow,
+        },
+        to,
+        area,
+        create_shadows,
+        dry_run,
+        files: expand_files(&files)?,
+    })
+}
+
+fn read_shadow_rules(path: Option<&str>, shadow_tiles: u32) -> Result<ShadowRules, String> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            return ShadowRules::default()
+                .check_shadows(shadow_tiles)
+                .map_err(|e| format!("invalid default shadow rules ({:?})", e))
+        }
+    };
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    ShadowRules::parse(&text)
+        .and_then(|rules| rules.check_shadows(shadow_tiles))
+        .map_err(|e| format!("{}: invalid shadow rules ({:?})", path, e))
+}
+
+fn parse_tile(text: &str) -> Result<(TextureType, u32), String> {
+    let (texture, id) = text
+        .split_once(':')
+        .ok_or(format!("invalid tile {}, expected e.g. floor:5", text))?;
+    let texture_type = match texture {
+        "floor" => TextureType::Floor,
+        "wall" | "walls" => TextureType::Walls,
+        _ => {
+            return Err(format!(
+                "invalid tile type {}, expected floor or wall",
+                texture
+            ))
+        }
+    };
+    Ok((texture_type, parse_number(id)?))
+}
+
+// Tiles outside the texture would be drawn from beyond its last row
+fn check_tile_id(tile: (TextureType, u32), number_of_tiles: u32) -> Result<(), String> {
+    if tile.1 >= number_of_tiles {
+        return Err(format!(
+            "invalid tile id {}, the {} texture has tiles 0-{}",
+            tile.1,
+            if tile.0 == TextureType::Walls {
+                "wall"
+            } else {
+                "floor"
+            },
+            number_of_tiles - 1
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_options, run};
+    use crate::files::{read_level, write_level};
+    use common::level::Level;
+    use common::types::TextureType;
+    use std::fs;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn to_tile_must_be_in_the_texture() {
+        let options = parse_options(&args(
+            "--from floor:0 --to wall:159 --assets ../assets a.lev",
+        ))
+        .unwrap();
+        assert!(options.to == (TextureType::Walls, 159));
+        assert_eq!(
+            parse_options(&args(
+                "--from floor:0 --to wall:160 --assets ../assets a.lev"
+            ))
+            .err()
+            .unwrap(),
+            "invalid tile id 160, the wall texture has tiles 0-159"
+        );
+        assert!(
+            parse_options(&args("--from floor:0 --to floor:5 --assets missing a.lev")).is_err()
+        );
+    }
+
+    #[test]
+    fn shadow_rules_must_be_in_the_shadow_texture() {
+        let path = std::env::temp_dir().join(format!("utk-lev-rules-{}.txt", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let parse = |rules: &str| {
+            fs::write(&path, rules).unwrap();
+            parse_options(&args(&format!(
+                "--from floor:0 --to floor:1 --create-shadows --shadow-rules {} --assets ../assets a.lev",
+                path
+            )))
+        };
+        assert!(parse("shadow 111 6").is_ok());
+        let error = parse("shadow 111 7").err().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            error,
+            format!("{}: invalid shadow rules (InvalidShadow(7))", path)
+        );
+    }
+
+    #[test]
+    fn replaces_inside_area_and_writes_level() {
+        let path = std::env::temp_dir().join(format!("utk-lev-replace-{}.txt", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let mut level = Level::get_default_level((10, 8));
+        for i in 1..7 {
+            level.put_tile_to_level(i * 10 + i, Some(7), &TextureType::Walls);
+        }
+        write_level(&path, &level).unwrap();
+
+        run(&args(&format!(
+            "--from wall:7 --to floor:3 --area 0,0,3,3 --assets ../assets {}",
+            path
+        )))
+        .unwrap();
+        let level = read_level(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let ids = (1..7)
+            .map(|i| level.get_tile_match(&(i, i)).unwrap().id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [3, 3, 3, 7, 7, 7]);
+    }
+}
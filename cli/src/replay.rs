@@ -0,0 +1,215 @@
+use crate::files::find_sidecar;
+use crate::image::{create_context, load_textures, save_png};
+use common::context::Context;
+use common::editor::LevelWriter;
+use common::event::{Event, WindowEvent};
+use common::font::Font;
+use common::level::Level;
+use common::load_level::LevelLister;
+use common::recording::Recording;
+use common::software_renderer::{SoftwareRenderer, SoftwareTexture};
+use common::{RunState, State, TextInput};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const USAGE: &str = "usage: utk-lev replay [options] <recording>
+
+Replays an event recording from the desktop or web editor without a window.
+The editor starts with the same empty level as the frontends and saved levels
+are not written.
+
+options:
+  --output <file>          write the last rendered screen to a PNG image
+  --frames <dir>           write every rendered screen to a numbered PNG image
+  --levels <dir>           directory with the levels listed by the load level
+                           screen (default: .)
+  --assets <dir>           directory with the tile textures and font
+                           (default: assets)";
+
+struct Options {
+    output: Option<String>,
+    frames: Option<String>,
+    levels: String,
+    assets: String,
+    file: String,
+}
+
+// Lists the .LEV files of a directory in the same sorted order as the desktop editor
+struct DirectoryLevelLister {
+    directory: String,
+    files: Vec<String>,
+}
+
+impl DirectoryLevelLister {
+    fn path(&self, name: &str) -> String {
+        Path::new(&self.directory)
+            .join(name)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+impl LevelLister for DirectoryLevelLister {
+    fn refresh(&mut self) {
+        self.files = fs::read_dir(&self.directory)
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .filter(|entry| entry.metadata().is_ok_and(|m| m.is_file()))
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .filter(|name| name.to_uppercase().ends_with(".LEV"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.files.sort();
+    }
+
+    fn reset(&mut self) {}
+
+    fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    fn level_name(&self, index: usize) -> &str {
+        &self.files[index]
+    }
+
+    fn load_level(&self, index: usize) -> Vec<u8> {
+        fs::read(self.path(&self.files[index])).unwrap_or_default()
+    }
+
+    fn load_sidecar(&self, index: usize, extension: &str) -> Option<Vec<u8>> {
+        let level_name = &self.files[index];
+        let sidecar_path = find_sidecar(Path::new(&self.path(level_name)), extension);
+        fs::read(sidecar_path).ok()
+    }
+}
+
+// Replays must not overwrite the levels of whoever runs them
+struct SkippingLevelWriter;
+
+impl LevelWriter for SkippingLevelWriter {
+    fn write(filename: &str, level_data: &[u8]) -> io::Result<()> {
+        println!("skipped writing {} bytes to {}", level_data.len(), filename);
+        Ok(())
+    }
+
+    fn remove(filename: &str) -> io::Result<()> {
+        println!("skipped removing {}", filename);
+        Ok(())
+    }
+}
+
+struct NoTextInput;
+
+impl TextInput for NoTextInput {
+    fn start(&mut self) {}
+
+    fn stop(&mut self) {}
+}
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let options = parse_options(args)?;
+    let text = fs::read_to_string(&options.file).map_err(|e| format!("{}: {}", options.file, e))?;
+    let recording = Recording::parse(&text)
+        .map_err(|e| format!("{}:{}: {}", options.file, e.line, e.message))?;
+    if let Some(frames) = &options.frames {
+        fs::create_dir_all(frames).map_err(|e| format!("{}: {}", frames, e))?;
+    }
+
+    let mut renderer = SoftwareRenderer::new(recording.screen.0, recording.screen.1);
+    let level_lister = DirectoryLevelLister {
+        directory: options.levels.clone(),
+        files: Vec::new(),
+    };
+    let mut context = create_context(
+        &mut renderer,
+        &options.assets,
+        Level::get_default_level((32, 22)),
+        level_lister,
+        recording.render_multiplier,
+    )?;
+    context.level.create_shadows(&context.shadow_rules);
+
+    let mut state: State<SkippingLevelWriter> = State::new();
+    let mut frame = 0;
+    for (_, event) in recording.events {
+        let mut render = false;
+        if let Event::Window {
+            win_event: WindowEvent::Resized { width, height },
+        } = event
+        {
+            renderer = resize(&options.assets, &mut context, (width, height))?;
+            render = true;
+        }
+        match state.handle_event(&mut context, &mut NoTextInput, event) {
+            RunState::Quit => break,
+            RunState::Run { needs_render } => render |= needs_render,
+        }
+        if render {
+            state.render(&mut renderer, &context);
+            if let Some(frames) = &options.frames {
+                let path = Path::new(frames).join(format!("{:06}.png", frame));
+                save_png(&renderer, &path.to_string_lossy())?;
+                frame += 1;
+            }
+        }
+    }
+
+    if let Some(output) = &options.output {
+        state.render(&mut renderer, &context);
+        save_png(&renderer, output)?;
+        println!("{}: wrote final screen to {}", options.file, output);
+    }
+    if options.frames.is_some() {
+        println!("{}: wrote {} frames", options.file, frame);
+    }
+    Ok(())
+}
+
+// Textures belong to the renderer, so a new screen size needs them created again
+fn resize(
+    assets: &str,
+    context: &mut Context<DirectoryLevelLister, SoftwareTexture>,
+    (width, height): (u32, u32),
+) -> Result<SoftwareRenderer, String> {
+    let mut renderer = SoftwareRenderer::new(width, height);
+    let render_multiplier = context.graphics.render_multiplier;
+    context.graphics.resolution_x = width;
+    context.graphics.resolution_y = height;
+    context.font = Font::new(&mut renderer, &context.fn2, render_multiplier);
+    context.textures = load_textures(&mut renderer, assets, &context.fn2)?;
+    Ok(renderer)
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        output: None,
+        frames: None,
+        levels: ".".to_string(),
+        assets: "assets".to_string(),
+        file: String::new(),
+    };
+    let mut files = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--output" => options.output = Some(value()?.clone()),
+            "--frames" => options.frames = Some(value()?.clone()),
+            "--levels" => options.levels = value()?.clone(),
+            "--assets" => options.assets = value()?.clone(),
+            "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => files.push(arg.clone()),
+        }
+    }
+
+    match &files[..] {
+        [file] => options.file = file.clone(),
+        _ => return Err(USAGE.to_string()),
+    }
+    Ok(options)
+}
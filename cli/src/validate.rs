@@ -0,0 +1,302 @@
+use crate::files::{expand_files, is_text_level, read_level};
+use crate::image::get_number_of_tiles_in_file;
+use crate::json::Json;
+use common::level::{crates, Level, StaticCrate, TILE_SIZE};
+use common::level_dump::{dump_level, get_problems};
+use common::types::TextureType;
+use std::fs;
+use std::path::Path;
+
+const USAGE: &str = "usage: utk-lev validate [options] <files>...
+
+Checks that level files load and that their objects and values are inside the
+ranges the game and the editor support. Exits with an error if any file has
+problems. See utk-lev dump for the fields around problems in .LEV files.
+
+options:
+  --json                   print one JSON object per file instead of text
+  --assets <dir>           directory with the tile textures which the tile ids
+                           must be in (default: assets)";
+
+const MAX_SPOTLIGHT_INTENSITY: u8 = 9;
+const MAX_STEAM_RANGE: u8 = 6;
+const MAX_COMMENT_LENGTH: usize = 19;
+
+// Number of tiles in the textures of --assets, None if the texture could not be read
+struct TextureTiles {
+    floor: Option<u32>,
+    walls: Option<u32>,
+    shadows: Option<u32>,
+}
+
+impl TextureTiles {
+    fn load(assets: &str) -> TextureTiles {
+        let load = |name: &str| {
+            let path = Path::new(assets).join(name);
+            get_number_of_tiles_in_file(&path.to_string_lossy())
+                .map_err(|message| eprintln!("{}, tile ids are not checked against it", message))
+                .ok()
+        };
+        TextureTiles {
+            floor: load("FLOOR1.PNG"),
+            walls: load("WALLS1.PNG"),
+            shadows: load("SHADOWS_ALPHA.PNG"),
+        }
+    }
+}
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let mut json = false;
+    let mut assets = "assets".to_string();
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--assets" => {
+                assets = args
+                    .next()
+                    .ok_or(format!("missing value for {}", arg))?
+                    .clone()
+            }
+            "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => files.push(arg.clone()),
+        }
+    }
+    if files.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    let files = expand_files(&files)?;
+    let texture_tiles = TextureTiles::load(&assets);
+
+    let mut invalid = 0;
+    for file in &files {
+        let mut problems = find_file_problems(file);
+        match read_level(file) {
+            Ok(level) => problems.extend(find_problems(&level, &texture_tiles)),
+            // The decoded file already tells where loading fails
+            Err(_) if !problems.is_empty() => {}
+            Err(message) => problems.push(message),
+        }
+        if !problems.is_empty() {
+            invalid += 1;
+        }
+        if json {
+            println!(
+                "{}",
+                Json::Object(vec![
+                    ("file", file.as_str().into()),
+                    ("valid", problems.is_empty().into()),
+                    ("problems", problems.into()),
+                ])
+            );
+        } else if problems.is_empty() {
+            println!("{}: ok", file);
+        } else {
+            println!("{}: {} problems", file, problems.len());
+            for problem in problems {
+                println!("  {}", problem);
+            }
+        }
+    }
+    if invalid > 0 {
+        return Err(format!("{} of {} files are invalid", invalid, files.len()));
+    }
+    Ok(())
+}
+
+// Problems in the .LEV data which the loaded level does not show, such as trailing data or
+// objects replacing each other
+fn find_file_problems(file: &str) -> Vec<String> {
+    if is_text_level(file) {
+        return Vec::new();
+    }
+    let Ok(data) = fs::read(file) else {
+        return Vec::new();
+    };
+    get_problems(&dump_level(&data))
+        .map(|field| {
+            format!(
+                "offset {:#06x}, {}: {}",
+                field.offset,
+                field.name,
+                field.problem.as_deref().unwrap()
+            )
+        })
+        .collect()
+}
+
+fn find_problems(level: &Level, texture_tiles: &TextureTiles) -> Vec<String> {
+    let mut problems = Vec::new();
+    let (width, height) = (level.tiles[0].len() as u32, level.tiles.len() as u32);
+    // Player starts are tile coordinates, the other objects are positioned in level pixels
+    let inside_tiles = |(x, y): (u32, u32)| x < width && y < height;
+    let inside = |(x, y): (u32, u32)| x < width * TILE_SIZE && y < height * TILE_SIZE;
+
+    for y in 0..height {
+        for x in 0..width {
+            let tile = level.get_tile_match(&(x, y)).unwrap();
+            let (texture, tiles) = match tile.texture_type {
+                TextureType::Floor => ("floor", texture_tiles.floor),
+                TextureType::Walls => ("wall", texture_tiles.walls),
+                // Shadows are only drawn over floor and wall tiles
+                TextureType::Shadow => {
+                    problems.push(format!("tile {},{} has texture type shadow", x, y));
+                    ("shadow", None)
+                }
+            };
+            if tiles.is_some_and(|tiles| tile.id >= tiles) {
+                problems.push(format!(
+                    "tile {},{} has {} id {} outside the {} tiles of the texture",
+                    x,
+                    y,
+                    texture,
+                    tile.id,
+                    tiles.unwrap()
+                ));
+            }
+            // Shadow tile id + 1, 0 for no shadow
+            let shadow = tile.shadow.unwrap();
+            if let Some(shadows) = texture_tiles.shadows.filter(|shadows| shadow > *shadows) {
+                problems.push(format!(
+                    "tile {},{} has shadow {} outside the {} tiles of the shadow texture",
+                    x, y, shadow, shadows
+                ));
+            }
+        }
+    }
+    for (number, position) in [(1, level.p1_position), (2, level.p2_position)] {
+        if !inside_tiles(position) {
+            problems.push(format!(
+                "player {} start {},{} is outside the level",
+                number, position.0, position.1
+            ));
+        }
+    }
+    for (position, intensity) in &level.spotlights {
+        if !inside(*position) {
+            problems.push(format!(
+                "spotlight {},{} is outside the level",
+                position.0, position.1
+            ));
+        }
+        if *intensity > MAX_SPOTLIGHT_INTENSITY {
+            problems.push(format!(
+                "spotlight {},{} has invalid intensity {}",
+                position.0, position.1, intensity
+            ));
+        }
+    }
+    for (position, steam) in &level.steams {
+        if !inside(*position) {
+            problems.push(format!(
+                "steam {},{} is outside the level",
+                position.0, position.1
+            ));
+        }
+        if steam.range > MAX_STEAM_RANGE {
+            problems.push(format!(
+                "steam {},{} has invalid range {}",
+                position.0, position.1, steam.range
+            ));
+        }
+        if steam.angle >= 360 || steam.angle % 5 != 0 {
+            problems.push(format!(
+                "steam {},{} has invalid angle {}",
+                position.0, position.1, steam.angle
+            ));
+        }
+    }
+    for (position, crate_item) in &level.crates.staticc {
+        let variant = match crate_item.crate_variant {
+            StaticCrate::Normal => "normal",
+            StaticCrate::Deathmatch => "deathmatch",
+        };
+        if !inside(*position) {
+            problems.push(format!(
+                "{} crate {},{} is outside the level",
+                variant, position.0, position.1
+            ));
+        }
+        if crate_item.crate_type as usize >= crates(crate_item.crate_class).len() {
+            problems.push(format!(
+                "{} crate {},{} has invalid type {}",
+                variant, position.0, position.1, crate_item.crate_type
+            ));
+        }
+    }
+    let comment = &level.general_info.comment;
+    if comment.len() > MAX_COMMENT_LENGTH || !comment.is_ascii() {
+        problems.push(format!(
+            "comment {} is not at most {} ASCII characters",
+            comment, MAX_COMMENT_LENGTH
+        ));
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_problems, TextureTiles};
+    use common::level::Level;
+    use common::level_text::{format_level, parse_level};
+
+    const TEXTURE_TILES: TextureTiles = TextureTiles {
+        floor: Some(160),
+        walls: Some(160),
+        shadows: Some(6),
+    };
+
+    fn get_level_with_player_start(x: u32, y: u32) -> Level {
+        let text = format_level(&Level::get_default_level((16, 12)))
+            .replace("player 1 1 1", &format!("player 1 {} {}", x, y));
+        parse_level(&text).unwrap()
+    }
+
+    #[test]
+    fn player_starts_are_checked_in_tiles() {
+        assert!(find_problems(&get_level_with_player_start(15, 11), &TEXTURE_TILES).is_empty());
+        assert_eq!(
+            find_problems(&get_level_with_player_start(200, 100), &TEXTURE_TILES),
+            ["player 1 start 200,100 is outside the level"]
+        );
+        assert_eq!(
+            find_problems(&get_level_with_player_start(16, 0), &TEXTURE_TILES).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn tiles_must_be_drawable() {
+        let text = format_level(&Level::get_default_level((16, 12))).replacen(
+            "tiles w0 w1 w1",
+            "tiles f9999 w160 f0:7",
+            1,
+        );
+        let level = parse_level(&text).unwrap();
+        assert_eq!(
+            find_problems(&level, &TEXTURE_TILES),
+            [
+                "tile 0,0 has floor id 9999 outside the 160 tiles of the texture",
+                "tile 1,0 has wall id 160 outside the 160 tiles of the texture",
+                "tile 2,0 has shadow 7 outside the 6 tiles of the shadow texture",
+            ]
+        );
+        let unknown_textures = TextureTiles {
+            floor: None,
+            walls: None,
+            shadows: None,
+        };
+        assert!(find_problems(&level, &unknown_textures).is_empty());
+
+        // Version, width and height come before the texture type of the first tile
+        let mut data = Level::get_default_level((16, 12)).serialize();
+        data[12..16].copy_from_slice(&2u32.to_le_bytes());
+        let level = Level::deserialize(&data).unwrap();
+        assert_eq!(
+            find_problems(&level, &TEXTURE_TILES),
+            ["tile 0,0 has texture type shadow"]
+        );
+    }
+}
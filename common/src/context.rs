@@ -4,12 +4,15 @@ use crate::graphics::Graphics;
 use crate::level::Level;
 use crate::load_level::LevelLister;
 use crate::render::Texture;
+use crate::shadow_rules::ShadowRules;
 use crate::types::{TextureType, Trigonometry};
 
 pub struct Textures<T: Texture> {
     pub floor: T,
     pub walls: T,
     pub shadows: T,
+    pub light: T,
+    pub crates: T,
 }
 
 pub struct Context<L: LevelLister, T: Texture> {
@@ -23,8 +26,11 @@ pub struct Context<L: LevelLister, T: Texture> {
     pub texture_type_selected: TextureType,
     pub texture_type_scrolled: TextureType,
     pub mouse: (u32, u32),
+    pub viewport: (u32, u32), // level pixel at the top left corner of the screen
     pub level_save_name: String,
     pub saved_level_name: Option<String>,
+    pub unsaved_changes: bool,
     pub trigonometry: Trigonometry,
     pub automatic_shadows: bool,
+    pub shadow_rules: ShadowRules,
 }
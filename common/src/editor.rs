@@ -3,12 +3,17 @@ use std::marker::PhantomData;
 use crate::context::Context;
 use crate::event::{Event, Keycode, MouseButton};
 use crate::graphics::Graphics;
-use crate::level::{bullet_crates, energy_crates, weapon_crates, Steam};
-use crate::level::{crates, StaticCrateType};
+use crate::inspector::{adjust_with_wheel, Inspector};
+use crate::layers::Layers;
+use crate::level::StaticCrateType;
+use crate::level::{crates, Steam};
 use crate::level::{CrateClass, StaticCrate};
-use crate::level::{Level, TILE_SIZE};
+use crate::level::{Level, LevelObject, TileArea, TileMatch, SHADOW_LOCK_EXTENSION, TILE_SIZE};
 use crate::load_level::LevelLister;
-use crate::render::{highlight_selected_tile, Point, Rect, Renderer, RendererColor, Texture};
+use crate::minimap::Minimap;
+use crate::render::CRATE_ICON_SIZE;
+use crate::render::{get_crate_icon_rect, Color, Point, Rect, Renderer, RendererColor, Texture};
+use crate::status_bar;
 use crate::types::GameType;
 use crate::types::{Mode, TextureType};
 use crate::util::*;
@@ -33,18 +38,26 @@ enum ShadowPromptType {
     Disabled,
 }
 
+#[derive(PartialEq)]
+enum ReplaceTilesState {
+    Prompt,
+    SelectArea,
+}
+
 #[derive(PartialEq)]
 enum PromptType {
     None,
     NewLevel(NewLevelState),
     Save(SaveLevelType),
     CreateShadows(ShadowPromptType),
+    ClearShadowLocks,
+    ReplaceTiles(ReplaceTilesState),
+    SaveFailed(String), // file name
     Quit,
 }
 
 #[derive(PartialEq)]
 enum InsertState {
-    Instructions((u32, u32)), // level coordinates of currently manipulated item
     Place,
     Delete,
 }
@@ -56,14 +69,36 @@ enum InsertType {
     Steam(InsertState),
     NormalCrate(InsertState),
     DMCrate(InsertState),
+    SelectObjects,
+}
+
+struct TileReplace {
+    find: TileMatch,
+    match_shadow: bool,
+    replacement: (TextureType, u32),
+    area: Option<TileArea>,
+}
+
+impl TileReplace {
+    fn tile_match(&self) -> TileMatch {
+        TileMatch {
+            shadow: if self.match_shadow {
+                self.find.shadow
+            } else {
+                None
+            },
+            ..self.find
+        }
+    }
 }
 
 pub trait LevelWriter {
-    fn write(filename: &str, level_data: &[u8]);
+    fn write(filename: &str, level_data: &[u8]) -> std::io::Result<()>;
+    // Removing a file which does not exist is not an error
+    fn remove(filename: &str) -> std::io::Result<()>;
 }
 
 pub struct EditorState<W: LevelWriter> {
-    set_position: u8,
     mouse_left_click: Option<(u32, u32)>,
     mouse_right_click: bool,
     prompt: PromptType,
@@ -71,15 +106,42 @@ pub struct EditorState<W: LevelWriter> {
     new_level_size_x: String,
     new_level_size_y: String,
     drag_tiles: bool,
+    drag_object: Option<(LevelObject, (i64, i64))>, // grab offset from object position
+    drag_axis_origin: Option<(u32, u32)>,           // Shift-drags move objects only along one axis
+    aimed_steam: Option<(u32, u32)>,
+    steam_time: u32,                              // milliseconds of steam animation
+    pan_origin: Option<((u32, u32), (u32, u32))>, // mouse and viewport when panning started
+    tile_replace: Option<TileReplace>,
+    selection: Vec<LevelObject>,
+    inspector: Inspector,
+    minimap: Minimap,
+    layers: Layers,
     phantom: PhantomData<W>,
 }
 
+// Distance in level pixels from steam position to its furthest render circle per range step
+const STEAM_RANGE_STEP: f32 = 30.0;
+const STEAM_RENDER_CIRCLES: u32 = 6;
+
+// Steam animation: particles per steam and the time for a particle to reach the range
+const STEAM_PARTICLES: u32 = 12;
+const STEAM_PARTICLE_LIFETIME_MS: u32 = 1200;
+
+// Lighting preview: alpha of the darkness over the level and the light added by a spotlight
+// of intensity 0, growing with every intensity step
+const AMBIENT_DARKNESS: u8 = 176;
+const LIGHT_BASE_STRENGTH: u32 = 72;
+const LIGHT_STRENGTH_STEP: u32 = 20;
+
+// Level pixels scrolled per arrow key press and per mouse wheel step
+const KEY_SCROLL_STEP: i64 = 10;
+const WHEEL_SCROLL_STEP: i64 = 20;
+
 static DEFAULT_LEVEL_SIZE: (u32, u32) = (16, 12);
 
 impl<W: LevelWriter> EditorState<W> {
     pub fn new() -> Self {
         EditorState {
-            set_position: 0,
             mouse_left_click: None,
             mouse_right_click: false,
             prompt: PromptType::None,
@@ -87,6 +149,16 @@ impl<W: LevelWriter> EditorState<W> {
             new_level_size_x: DEFAULT_LEVEL_SIZE.0.to_string(),
             new_level_size_y: DEFAULT_LEVEL_SIZE.1.to_string(),
             drag_tiles: false,
+            drag_object: None,
+            drag_axis_origin: None,
+            aimed_steam: None,
+            steam_time: 0,
+            pan_origin: None,
+            tile_replace: None,
+            selection: Vec::new(),
+            inspector: Inspector::default(),
+            minimap: Minimap::default(),
+            layers: Layers::default(),
             phantom: PhantomData,
         }
     }
@@ -97,22 +169,35 @@ impl<W: LevelWriter> EditorState<W> {
         text_input: &mut I,
         event: Event,
     ) -> EventResult {
+        self.selection
+            .retain(|object| context.level.has_object(object));
+        if let Event::KeyDown { keycode, modifiers } = &event {
+            if self.prompt == PromptType::None
+                && !modifiers.ctrl
+                && !modifiers.alt
+                && (self.layers.handle_key(keycode)
+                    || self.inspector.handle_key(context, &self.selection, keycode))
+            {
+                return EventResult::KeepMode;
+            }
+        }
         match event {
             Event::Quit
             | Event::KeyDown {
                 keycode: Keycode::Escape,
+                ..
             } => {
-                self.prompt = if self.prompt != PromptType::None
-                    || self.insert_item != InsertType::None
-                    || self.set_position > 0
-                {
-                    self.insert_item = InsertType::None;
-                    text_input.stop();
-                    self.set_position = 0;
-                    PromptType::None
-                } else {
-                    PromptType::Quit
-                };
+                self.prompt =
+                    if self.prompt != PromptType::None || self.insert_item != InsertType::None {
+                        self.insert_item = InsertType::None;
+                        text_input.stop();
+                        PromptType::None
+                    } else if !self.selection.is_empty() {
+                        self.selection.clear();
+                        PromptType::None
+                    } else {
+                        PromptType::Quit
+                    };
             }
             Event::TextInput { text, .. } => match &self.prompt {
                 PromptType::NewLevel(new_level_state) => match new_level_state {
@@ -130,7 +215,15 @@ impl<W: LevelWriter> EditorState<W> {
                 _ => return EventResult::EventIgnored,
             },
             Event::Window { .. } => return EventResult::EventIgnored,
-            Event::KeyDown { keycode, .. } => match keycode {
+            Event::KeyDown { keycode, modifiers } => match keycode {
+                Keycode::S if modifiers.ctrl => {
+                    if context.level_save_name.len() > 1 {
+                        self.save_level(context, text_input);
+                    } else {
+                        self.prompt = PromptType::Save(SaveLevelType::NameInput);
+                        text_input.start();
+                    }
+                }
                 Keycode::Space => {
                     return EventResult::ChangeMode(Mode::TileSelect);
                 }
@@ -150,6 +243,10 @@ impl<W: LevelWriter> EditorState<W> {
                     self.new_level_size_x = DEFAULT_LEVEL_SIZE.0.to_string();
                     self.new_level_size_y = DEFAULT_LEVEL_SIZE.1.to_string();
                 }
+                Keycode::F5 => {
+                    text_input.stop();
+                    self.prompt = PromptType::ClearShadowLocks;
+                }
                 Keycode::F6 => {
                     text_input.stop();
                     self.prompt = PromptType::CreateShadows(if context.automatic_shadows {
@@ -167,15 +264,6 @@ impl<W: LevelWriter> EditorState<W> {
                 Keycode::F9 => {
                     return EventResult::ChangeMode(Mode::RandomItemEditor(GameType::Deathmatch));
                 }
-                Keycode::Num1 | Keycode::Num2 => match self.prompt {
-                    PromptType::NewLevel(_) | PromptType::Save(_) => {
-                        return EventResult::EventIgnored
-                    }
-                    _ => {
-                        self.set_position = if keycode == Keycode::Num1 { 1 } else { 2 };
-                        self.prompt = PromptType::None;
-                    }
-                },
                 Keycode::Q | Keycode::W => match self.prompt {
                     PromptType::Save(_) => return EventResult::EventIgnored,
                     _ => {
@@ -190,6 +278,16 @@ impl<W: LevelWriter> EditorState<W> {
                 },
                 Keycode::A | Keycode::S => match self.prompt {
                     PromptType::Save(_) => return EventResult::EventIgnored,
+                    PromptType::ReplaceTiles(ReplaceTilesState::Prompt) => {
+                        let tile_replace = self.tile_replace.as_mut().unwrap();
+                        if keycode == Keycode::S {
+                            tile_replace.match_shadow = !tile_replace.match_shadow;
+                        } else if tile_replace.area.is_some() {
+                            tile_replace.area = None;
+                        } else {
+                            self.prompt = PromptType::ReplaceTiles(ReplaceTilesState::SelectArea);
+                        }
+                    }
                     _ => {
                         self.insert_item = if keycode == Keycode::A {
                             InsertType::Steam(InsertState::Place)
@@ -214,12 +312,73 @@ impl<W: LevelWriter> EditorState<W> {
                         self.prompt = PromptType::None;
                     }
                 },
+                Keycode::B => match self.prompt {
+                    PromptType::Save(_) => return EventResult::EventIgnored,
+                    _ => {
+                        self.insert_item = InsertType::SelectObjects;
+                        text_input.stop();
+                        self.prompt = PromptType::None;
+                    }
+                },
+                Keycode::L => match self.prompt {
+                    PromptType::Save(_) => return EventResult::EventIgnored,
+                    _ => self.layers.toggle_menu(),
+                },
+                Keycode::M => match self.prompt {
+                    PromptType::Save(_) => return EventResult::EventIgnored,
+                    _ => self.minimap.toggle(),
+                },
+                Keycode::Delete => {
+                    if self.prompt != PromptType::None || self.selection.is_empty() {
+                        return EventResult::EventIgnored;
+                    }
+                    for object in &self.selection {
+                        context.level.delete_object(object);
+                    }
+                    self.selection.clear();
+                    context.unsaved_changes = true;
+                }
+                Keycode::R => match self.prompt {
+                    PromptType::NewLevel(_) | PromptType::Save(_) => {
+                        return EventResult::EventIgnored
+                    }
+                    _ => {
+                        if context.texture_type_selected == TextureType::Shadow {
+                            return EventResult::EventIgnored;
+                        }
+                        let mouse = get_limited_screen_level_size(
+                            &context.graphics,
+                            &context.mouse,
+                            &context.level,
+                            &context.viewport,
+                        );
+                        let pointed_tile = get_logical_coordinates(
+                            &context.graphics,
+                            mouse.0,
+                            mouse.1,
+                            Some(context.viewport),
+                        );
+                        let find = match context.level.get_tile_match(&pointed_tile) {
+                            Some(find) => find,
+                            None => return EventResult::EventIgnored,
+                        };
+                        self.tile_replace = Some(TileReplace {
+                            find,
+                            match_shadow: false,
+                            replacement: (context.texture_type_selected, context.selected_tile_id),
+                            area: None,
+                        });
+                        self.insert_item = InsertType::None;
+                        text_input.stop();
+                        self.prompt = PromptType::ReplaceTiles(ReplaceTilesState::Prompt);
+                    }
+                },
                 Keycode::Y => match self.prompt {
                     PromptType::NewLevel(NewLevelState::Prompt) => {
                         self.prompt = PromptType::NewLevel(NewLevelState::XSize);
                         text_input.start();
                     }
-                    PromptType::Save(SaveLevelType::Prompt) => {
+                    PromptType::Save(SaveLevelType::Prompt) | PromptType::SaveFailed(_) => {
                         self.prompt = PromptType::Save(SaveLevelType::NameInput);
                         text_input.start();
                     }
@@ -227,210 +386,74 @@ impl<W: LevelWriter> EditorState<W> {
                         context.automatic_shadows = match shadow_state {
                             ShadowPromptType::Enabled => false,
                             ShadowPromptType::Disabled => {
-                                context.level.create_shadows();
+                                context.level.create_shadows(&context.shadow_rules);
+                                context.unsaved_changes = true;
                                 true
                             }
                         };
                         self.prompt = PromptType::None;
                     }
-                    PromptType::Quit => return EventResult::Quit,
-                    PromptType::None => {
-                        self.prompt = PromptType::None;
-                    }
-                    _ => return EventResult::EventIgnored,
-                },
-                Keycode::Up => match &self.insert_item {
-                    InsertType::Spotlight(state) => {
-                        if let InsertState::Instructions(coordinates) = state {
-                            let spotlight_intensity =
-                                context.level.get_spotlight_from_level(coordinates);
-                            context
-                                .level
-                                .put_spotlight_to_level(coordinates, spotlight_intensity + 1)
-                        }
-                    }
-                    InsertType::Steam(state) => {
-                        if let InsertState::Instructions(coordinates) = state {
-                            let steam = context.level.get_steam_from_level(coordinates);
-                            if steam.range < 6 {
-                                context.level.put_steam_to_level(
-                                    coordinates,
-                                    &Steam {
-                                        angle: steam.angle,
-                                        range: steam.range + 1,
-                                    },
-                                )
-                            }
-                        }
-                    }
-                    InsertType::NormalCrate(state) | InsertType::DMCrate(state) => {
-                        if let InsertState::Instructions(coordinates) = state {
-                            let mut crate_item = *context.level.get_crate_from_level(coordinates);
-                            if (crate_item.crate_class as u32) < CrateClass::Energy as u32 {
-                                crate_item.crate_type = 0;
-                                crate_item.crate_class =
-                                    CrateClass::from_u32(crate_item.crate_class as u32 + 1);
-                                context.level.put_crate_to_level(coordinates, &crate_item)
-                            }
-                        }
-                    }
-                    _ => {
-                        if context.level.scroll.1 > 0 {
-                            context.level.scroll.1 -= 1
-                        }
-                    }
-                },
-                Keycode::Down => match &self.insert_item {
-                    InsertType::Spotlight(state) => {
-                        if let InsertState::Instructions(coordinates) = state {
-                            let spotlight_intensity =
-                                context.level.get_spotlight_from_level(coordinates);
-                            if spotlight_intensity > 0 {
-                                context
-                                    .level
-                                    .put_spotlight_to_level(coordinates, spotlight_intensity - 1)
-                            }
-                        }
-                    }
-                    InsertType::Steam(state) => {
-                        if let InsertState::Instructions(coordinates) = state {
-                            let steam = context.level.get_steam_from_level(coordinates);
-                            if steam.range > 0 {
-                                context.level.put_steam_to_level(
-                                    coordinates,
-                                    &Steam {
-                                        angle: steam.angle,
-                                        range: steam.range - 1,
-                                    },
-                                )
-                            }
-                        }
-                    }
-                    InsertType::NormalCrate(state) | InsertType::DMCrate(state) => {
-                        if let InsertState::Instructions(coordinates) = state {
-                            let mut crate_item = *context.level.get_crate_from_level(coordinates);
-                            if crate_item.crate_class as u32 > 0 {
-                                crate_item.crate_type = 0;
-                                crate_item.crate_class =
-                                    CrateClass::from_u32(crate_item.crate_class as u32 - 1);
-                                context.level.put_crate_to_level(coordinates, &crate_item)
-                            }
-                        }
-                    }
-                    _ => {
-                        if context.level.scroll.1 + context.graphics.get_full_y_tiles_per_screen()
-                            < (context.level.tiles.len()) as u32
-                        {
-                            context.level.scroll.1 += 1;
-                        }
-                    }
-                },
-                Keycode::Left => match &self.insert_item {
-                    InsertType::Steam(state) => {
-                        if let InsertState::Instructions(coordinates) = state {
-                            let steam = context.level.get_steam_from_level(coordinates);
-                            context.level.put_steam_to_level(
-                                coordinates,
-                                &Steam {
-                                    angle: (steam.angle + 360 - 5) % 360,
-                                    range: steam.range,
-                                },
-                            )
-                        }
-                    }
-                    InsertType::NormalCrate(state) | InsertType::DMCrate(state) => {
-                        if let InsertState::Instructions(coordinates) = state {
-                            let mut crate_item = *context.level.get_crate_from_level(coordinates);
-                            if crate_item.crate_type > 0 {
-                                crate_item.crate_type -= 1;
-                                context.level.put_crate_to_level(coordinates, &crate_item);
-                            }
-                        }
-                    }
-                    _ => {
-                        if context.level.scroll.0 > 0 {
-                            context.level.scroll.0 -= 1;
-                        }
-                    }
-                },
-                Keycode::Right => match &self.insert_item {
-                    InsertType::Steam(state) => {
-                        if let InsertState::Instructions(coordinates) = state {
-                            let steam = context.level.get_steam_from_level(coordinates);
-                            context.level.put_steam_to_level(
-                                coordinates,
-                                &Steam {
-                                    angle: (steam.angle + 5) % 360,
-                                    range: steam.range,
-                                },
-                            )
+                    PromptType::ClearShadowLocks => {
+                        context.level.shadow_locks.clear();
+                        if context.automatic_shadows {
+                            context.level.create_shadows(&context.shadow_rules);
                         }
+                        context.unsaved_changes = true;
+                        self.prompt = PromptType::None;
                     }
-                    InsertType::NormalCrate(state) | InsertType::DMCrate(state) => {
-                        if let InsertState::Instructions(coordinates) = state {
-                            let mut crate_item = *context.level.get_crate_from_level(coordinates);
-                            if crate_item.crate_type
-                                < (crates(crate_item.crate_class).len() - 1) as u8
-                            {
-                                crate_item.crate_type += 1;
-                                context.level.put_crate_to_level(coordinates, &crate_item);
-                            }
+                    PromptType::ReplaceTiles(ReplaceTilesState::Prompt) => {
+                        let tile_replace = self.tile_replace.as_ref().unwrap();
+                        context.level.replace_tiles(
+                            &tile_replace.tile_match(),
+                            tile_replace.replacement,
+                            tile_replace.area,
+                        );
+                        if context.automatic_shadows {
+                            context.level.create_shadows(&context.shadow_rules);
                         }
+                        context.unsaved_changes = true;
+                        self.tile_replace = None;
+                        self.prompt = PromptType::None;
                     }
-                    _ => {
-                        if context.level.scroll.0 + context.graphics.get_full_x_tiles_per_screen()
-                            < (context.level.tiles[0].len()) as u32
-                        {
-                            context.level.scroll.0 += 1;
-                        }
+                    PromptType::Quit => return EventResult::Quit,
+                    PromptType::None => {
+                        self.prompt = PromptType::None;
                     }
+                    _ => return EventResult::EventIgnored,
                 },
-                Keycode::Return | Keycode::KpEnter => match self.insert_item {
-                    InsertType::Spotlight(InsertState::Instructions(_)) => {
-                        self.insert_item = InsertType::Spotlight(InsertState::Place);
-                    }
-                    InsertType::Steam(InsertState::Instructions(_)) => {
-                        self.insert_item = InsertType::Steam(InsertState::Place);
+                Keycode::Up => scroll_level(context, (0, -KEY_SCROLL_STEP)),
+                Keycode::Down => scroll_level(context, (0, KEY_SCROLL_STEP)),
+                Keycode::Left => scroll_level(context, (-KEY_SCROLL_STEP, 0)),
+                Keycode::Right => scroll_level(context, (KEY_SCROLL_STEP, 0)),
+                Keycode::Return | Keycode::KpEnter => match self.prompt {
+                    PromptType::NewLevel(NewLevelState::XSize)
+                        if self.new_level_size_x.len() > 1
+                            && self.new_level_size_x.parse::<u8>().unwrap() >= 16 =>
+                    {
+                        self.prompt = PromptType::NewLevel(NewLevelState::YSize);
                     }
-                    InsertType::NormalCrate(InsertState::Instructions(_)) => {
-                        self.insert_item = InsertType::NormalCrate(InsertState::Place);
+                    PromptType::NewLevel(NewLevelState::YSize)
+                        if self.new_level_size_x.len() > 1
+                            && self.new_level_size_y.parse::<u8>().unwrap() >= 12 =>
+                    {
+                        context.level = Level::get_default_level((
+                            self.new_level_size_x.parse::<u8>().unwrap(),
+                            self.new_level_size_y.parse::<u8>().unwrap(),
+                        ));
+                        context.level.create_shadows(&context.shadow_rules);
+                        context.viewport = (0, 0);
+                        context.unsaved_changes = false;
+                        text_input.stop();
+                        context.saved_level_name = None;
+                        context.level_save_name.clear();
+                        self.prompt = PromptType::None;
                     }
-                    InsertType::DMCrate(InsertState::Instructions(_)) => {
-                        self.insert_item = InsertType::DMCrate(InsertState::Place);
+                    PromptType::Save(SaveLevelType::NameInput)
+                        if context.level_save_name.len() > 1 =>
+                    {
+                        self.save_level(context, text_input);
                     }
-                    _ => match self.prompt {
-                        PromptType::NewLevel(NewLevelState::XSize)
-                            if self.new_level_size_x.len() > 1
-                                && self.new_level_size_x.parse::<u8>().unwrap() >= 16 =>
-                        {
-                            self.prompt = PromptType::NewLevel(NewLevelState::YSize);
-                        }
-                        PromptType::NewLevel(NewLevelState::YSize)
-                            if self.new_level_size_x.len() > 1
-                                && self.new_level_size_y.parse::<u8>().unwrap() >= 12 =>
-                        {
-                            context.level = Level::get_default_level((
-                                self.new_level_size_x.parse::<u8>().unwrap(),
-                                self.new_level_size_y.parse::<u8>().unwrap(),
-                            ));
-                            text_input.stop();
-                            context.saved_level_name = None;
-                            context.level_save_name.clear();
-                            self.prompt = PromptType::None;
-                        }
-                        PromptType::Save(SaveLevelType::NameInput)
-                            if context.level_save_name.len() > 1 =>
-                        {
-                            let level_save_name_uppercase = context.level_save_name.to_uppercase();
-                            let level_saved_name = format!("{}.LEV", &level_save_name_uppercase);
-                            let level_data = context.level.serialize();
-                            W::write(&level_saved_name, &level_data);
-                            text_input.stop();
-                            context.saved_level_name = Some(level_saved_name.to_lowercase());
-                            self.prompt = PromptType::None;
-                        }
-                        _ => return EventResult::EventIgnored,
-                    },
+                    _ => return EventResult::EventIgnored,
                 },
                 Keycode::Backspace => match &self.prompt {
                     PromptType::NewLevel(new_level_state) => match new_level_state {
@@ -447,23 +470,8 @@ impl<W: LevelWriter> EditorState<W> {
                     }
                     _ => return EventResult::EventIgnored,
                 },
-                Keycode::Plus | Keycode::KpPlus => {
-                    if !context.graphics.supports_scaling {
-                        return EventResult::EventIgnored;
-                    }
-                    if context.graphics.render_multiplier == 1 {
-                        context.graphics.render_multiplier = 2;
-                    }
-                }
-                Keycode::Minus | Keycode::KpMinus => {
-                    if !context.graphics.supports_scaling {
-                        return EventResult::EventIgnored;
-                    }
-                    if context.graphics.render_multiplier == 2 {
-                        context.graphics.render_multiplier = 1;
-                        context.level.scroll = (0, 0);
-                    }
-                }
+                Keycode::Plus | Keycode::KpPlus => zoom_level(context, true),
+                Keycode::Minus | Keycode::KpMinus => zoom_level(context, false),
                 _ => {
                     if self.prompt != PromptType::NewLevel(NewLevelState::XSize)
                         && self.prompt != PromptType::NewLevel(NewLevelState::YSize)
@@ -476,6 +484,21 @@ impl<W: LevelWriter> EditorState<W> {
             Event::MouseMotion { x, y, .. } => {
                 context.mouse.0 = x as u32;
                 context.mouse.1 = y as u32;
+                if let Some(center) = self.minimap.handle_mouse_motion(context) {
+                    center_viewport(context, &center);
+                    return EventResult::KeepMode;
+                }
+                if let Some((mouse, viewport)) = self.pan_origin {
+                    let zoom = context.graphics.zoom as i64;
+                    context.viewport = viewport;
+                    scroll_level(
+                        context,
+                        (
+                            (mouse.0 as i64 - x as i64) * 100 / zoom,
+                            (mouse.1 as i64 - y as i64) * 100 / zoom,
+                        ),
+                    );
+                }
                 if self.mouse_left_click.is_some() {
                     self.handle_mouse_left_down(context);
                 }
@@ -485,14 +508,88 @@ impl<W: LevelWriter> EditorState<W> {
             }
             Event::MouseButtonDown {
                 button: MouseButton::Left,
+                modifiers,
             } => {
+                if self.inspector.handle_click(context, &self.selection) {
+                    return EventResult::KeepMode;
+                }
+                if self.layers.handle_click(context) {
+                    return EventResult::KeepMode;
+                }
+                if let Some(center) = self.minimap.handle_mouse_down(context) {
+                    center_viewport(context, &center);
+                    return EventResult::KeepMode;
+                }
+                self.drag_axis_origin = if modifiers.shift {
+                    Some(get_level_coordinates_from_screen_coordinates(
+                        &context.graphics,
+                        &context.mouse,
+                        &context.viewport,
+                    ))
+                } else {
+                    None
+                };
                 self.mouse_left_click = Some(context.mouse);
                 self.handle_mouse_left_down(context);
             }
             Event::MouseButtonUp {
                 button: MouseButton::Left,
             } => {
-                if self.drag_tiles {
+                if self.minimap.handle_mouse_up() {
+                    return EventResult::KeepMode;
+                }
+                if self.insert_item == InsertType::SelectObjects {
+                    if let Some(coordinates) = self.mouse_left_click {
+                        let p0 = get_level_coordinates_from_screen_coordinates(
+                            &context.graphics,
+                            &coordinates,
+                            &context.viewport,
+                        );
+                        let p1 = get_level_coordinates_from_screen_coordinates(
+                            &context.graphics,
+                            &context.mouse,
+                            &context.viewport,
+                        );
+                        self.set_selection(context.level.find_objects_in_area(
+                            &(std::cmp::min(p0.0, p1.0), std::cmp::min(p0.1, p1.1)),
+                            &(std::cmp::max(p0.0, p1.0), std::cmp::max(p0.1, p1.1)),
+                        ));
+                    }
+                    self.insert_item = InsertType::None;
+                } else if self.drag_tiles
+                    && self.prompt == PromptType::ReplaceTiles(ReplaceTilesState::SelectArea)
+                {
+                    self.drag_tiles = false;
+                    if let Some(coordinates) = self.mouse_left_click {
+                        let p0 = get_limited_screen_level_size(
+                            &context.graphics,
+                            &coordinates,
+                            &context.level,
+                            &context.viewport,
+                        );
+                        let p1 = get_limited_screen_level_size(
+                            &context.graphics,
+                            &context.mouse,
+                            &context.level,
+                            &context.viewport,
+                        );
+                        let viewport = Some(context.viewport);
+                        let (x0, y0) = get_logical_coordinates(
+                            &context.graphics,
+                            std::cmp::min(p0.0, p1.0),
+                            std::cmp::min(p0.1, p1.1),
+                            viewport,
+                        );
+                        let (x1, y1) = get_logical_coordinates(
+                            &context.graphics,
+                            std::cmp::max(p0.0, p1.0),
+                            std::cmp::max(p0.1, p1.1),
+                            viewport,
+                        );
+                        self.tile_replace.as_mut().unwrap().area = Some(((x0, y0), (x1, y1)));
+                    }
+                    self.prompt = PromptType::ReplaceTiles(ReplaceTilesState::Prompt);
+                } else if self.drag_tiles {
                     self.drag_tiles = false;
                     if let Some(coordinates) = self.mouse_left_click {
                         let selected_level_tiles = get_selected_level_tiles(
@@ -501,35 +598,48 @@ impl<W: LevelWriter> EditorState<W> {
                                 &context.graphics,
                                 &coordinates,
                                 &context.level,
-                                context.graphics.get_render_size(),
+                                &context.viewport,
                             ),
                             &get_limited_screen_level_size(
                                 &context.graphics,
                                 &context.mouse,
                                 &context.level,
-                                context.graphics.get_render_size(),
+                                &context.viewport,
                             ),
                             context.level.tiles[0].len() as u32,
-                            Some(context.level.scroll),
+                            Some(context.viewport),
                         );
-                        for level_tile_id in selected_level_tiles {
+                        for level_tile_id in &selected_level_tiles {
                             context.level.put_tile_to_level(
-                                level_tile_id,
+                                *level_tile_id,
                                 Some(context.selected_tile_id),
                                 &context.texture_type_selected,
                             );
                         }
-                        if context.texture_type_selected == TextureType::Shadow {
-                            context.automatic_shadows = false;
-                        } else if context.automatic_shadows {
-                            context.level.create_shadows();
+                        context.unsaved_changes = true;
+                        if context.texture_type_selected != TextureType::Shadow
+                            && context.automatic_shadows
+                        {
+                            let level_width = context.level.tiles[0].len() as u32;
+                            let first = selected_level_tiles[0];
+                            let last = selected_level_tiles[selected_level_tiles.len() - 1];
+                            context.level.create_shadows_for_area(
+                                (
+                                    (first % level_width, first / level_width),
+                                    (last % level_width, last / level_width),
+                                ),
+                                &context.shadow_rules,
+                            );
                         }
                     }
                 };
+                self.drag_object = None;
+                self.aimed_steam = None;
                 self.mouse_left_click = None;
             }
             Event::MouseButtonDown {
                 button: MouseButton::Right,
+                ..
             } => {
                 self.mouse_right_click = true;
                 self.handle_mouse_right_down(context);
@@ -539,212 +649,163 @@ impl<W: LevelWriter> EditorState<W> {
             } => {
                 self.mouse_right_click = false;
             }
+            Event::MouseButtonDown {
+                button: MouseButton::Middle,
+                ..
+            } => {
+                self.pan_origin = Some((context.mouse, context.viewport));
+            }
+            Event::MouseButtonUp {
+                button: MouseButton::Middle,
+            } => {
+                self.pan_origin = None;
+            }
+            Event::Tick { milliseconds } => {
+                if !self.is_animating(context) {
+                    return EventResult::EventIgnored;
+                }
+                self.steam_time = self.steam_time.wrapping_add(milliseconds);
+            }
+            Event::MouseWheel { x, y, modifiers } => {
+                let hovered_object = context.level.find_object(
+                    &get_level_coordinates_from_screen_coordinates(
+                        &context.graphics,
+                        &context.mouse,
+                        &context.viewport,
+                    ),
+                    context.graphics.zoom,
+                );
+                if modifiers.ctrl {
+                    if y != 0 {
+                        zoom_level(context, y > 0);
+                    }
+                } else if hovered_object.is_some_and(|object| self.selection.contains(&object)) {
+                    adjust_with_wheel(&mut context.level, &self.selection, y);
+                    context.unsaved_changes = true;
+                } else if modifiers.shift {
+                    // Browsers report Shift+wheel as horizontal scrolling
+                    let amount = if y != 0 { y } else { x };
+                    scroll_level(context, (-amount as i64 * WHEEL_SCROLL_STEP, 0));
+                } else {
+                    scroll_level(
+                        context,
+                        (-x as i64 * WHEEL_SCROLL_STEP, -y as i64 * WHEEL_SCROLL_STEP),
+                    );
+                }
+            }
             _ => return EventResult::EventIgnored,
         };
         EventResult::KeepMode
     }
 
+    pub fn is_animating<L: LevelLister, T: Texture>(&self, context: &Context<L, T>) -> bool {
+        self.layers.steam_animation && !context.level.steams.is_empty()
+    }
+
     pub fn render<L: LevelLister, R: Renderer>(
         &mut self,
         renderer: &mut R,
         context: &Context<L, R::Texture>,
     ) {
-        self.render_level(renderer, context);
-
-        let highlighted_id = get_tile_id_from_coordinates(
-            &context.graphics,
-            &get_limited_screen_level_size(
-                &context.graphics,
-                &context.mouse,
-                &context.level,
-                context.graphics.get_render_size(),
-            ),
-            context.graphics.get_x_tiles_per_screen(),
-            None,
-        );
-        highlight_selected_tile(
+        let mut labeled_objects = self.selection.clone();
+        labeled_objects.extend(self.get_hovered_object(context));
+        render_level(
             renderer,
-            &context.graphics,
-            highlighted_id,
-            RendererColor::White,
+            context,
+            &self.layers,
+            self.steam_time,
+            &labeled_objects,
         );
-        let render_size = context.graphics.get_render_size();
-        context.font.render_text_relative(
-            renderer,
-            "PL1",
-            context.level.origo(render_size),
-            (
-                context.level.p1_position.0 * render_size,
-                context.level.p1_position.1 * render_size,
-            ),
-        );
-        context.font.render_text_relative(
-            renderer,
-            "PL2",
-            context.level.origo(render_size),
-            (
-                context.level.p2_position.0 * render_size,
-                context.level.p2_position.1 * render_size,
-            ),
+
+        let mouse = get_limited_screen_level_size(
+            &context.graphics,
+            &context.mouse,
+            &context.level,
+            &context.viewport,
         );
-        let text = if self.set_position == 1 {
-            "place PL1 start point"
-        } else if self.set_position == 2 {
-            "place PL2 start point"
+        let highlighted_tile =
+            get_logical_coordinates(&context.graphics, mouse.0, mouse.1, Some(context.viewport));
+        highlight_level_tile(renderer, context, &highlighted_tile, RendererColor::White);
+        if let Some(object) = self.get_hovered_object(context) {
+            render_object_highlight(renderer, context, &object);
+        }
+        self.selection
+            .retain(|object| context.level.has_object(object));
+        for object in &self.selection {
+            render_object_highlight(renderer, context, object);
+        }
+        let text = if self.prompt == PromptType::ReplaceTiles(ReplaceTilesState::SelectArea) {
+            "drag to select area to replace in (ESC to cancel)"
         } else {
             match self.insert_item {
-                InsertType::Spotlight(InsertState::Instructions(_)) => {
-                    "use UP and DOWN keys to adjust size, ENTER to accept"
-                }
                 InsertType::Spotlight(InsertState::Place) => "place spotlight (ESC to cancel)",
                 InsertType::Spotlight(InsertState::Delete) => "delete spotlight (ESC to cancel)",
-                InsertType::Steam(InsertState::Instructions(_)) => {
-                    "UP/DOWN: range, LEFT/RIGHT: dir, ENTER to accept"
-                }
-                InsertType::Steam(InsertState::Place) => "place steam (ESC to cancel)",
+                InsertType::Steam(InsertState::Place) => "place steam, drag to aim (ESC to cancel)",
                 InsertType::Steam(InsertState::Delete) => "delete steam (ESC to cancel)",
                 InsertType::NormalCrate(InsertState::Place) => "place normal game crate",
                 InsertType::DMCrate(InsertState::Place) => "place deathmatch game crate",
-                InsertType::NormalCrate(InsertState::Instructions(_))
-                | InsertType::DMCrate(InsertState::Instructions(_)) => {
-                    "UP/DOWN/LEFT/RIGHT: select CRATE, ENTER to accept"
-                }
                 InsertType::NormalCrate(InsertState::Delete)
                 | InsertType::DMCrate(InsertState::Delete) => "delete crate",
+                InsertType::SelectObjects => "drag to select objects (ESC to cancel)",
                 _ => "F1 for help",
             }
         };
         context
             .font
             .render_text(renderer, text, (context.font.px(4), context.font.px(4)));
+        status_bar::render(
+            renderer,
+            context,
+            self.get_tool_name(),
+            self.get_hovered_object(context),
+        );
+        self.minimap.render(renderer, context);
+        self.inspector.render(renderer, context, &self.selection);
+        self.layers.render_menu(renderer, context);
         self.render_prompt_if_needed(renderer, context);
-        if self.insert_item == InsertType::None {
+        if self.insert_item == InsertType::SelectObjects {
             if let Some(coordinates) = self.mouse_left_click {
-                let selected_screen_tiles = get_selected_level_tiles(
+                renderer.draw_rect(
+                    &Rect::new(
+                        std::cmp::min(coordinates.0, context.mouse.0) as i32,
+                        std::cmp::min(coordinates.1, context.mouse.1) as i32,
+                        coordinates.0.abs_diff(context.mouse.0),
+                        coordinates.1.abs_diff(context.mouse.1),
+                    ),
+                    RendererColor::White,
+                );
+            }
+        }
+        if self.insert_item == InsertType::None && self.drag_object.is_none() {
+            if let Some(coordinates) = self.mouse_left_click {
+                let level_width = context.level.tiles[0].len() as u32;
+                let selected_level_tiles = get_selected_level_tiles(
                     &context.graphics,
                     &get_limited_screen_level_size(
                         &context.graphics,
                         &coordinates,
                         &context.level,
-                        context.graphics.get_render_size(),
+                        &context.viewport,
                     ),
                     &get_limited_screen_level_size(
                         &context.graphics,
                         &context.mouse,
                         &context.level,
-                        context.graphics.get_render_size(),
+                        &context.viewport,
                     ),
-                    context.graphics.get_x_tiles_per_screen(),
-                    None,
+                    level_width,
+                    Some(context.viewport),
                 );
-                for screen_tile_id in selected_screen_tiles {
-                    highlight_selected_tile(
+                for level_tile_id in selected_level_tiles {
+                    highlight_level_tile(
                         renderer,
-                        &context.graphics,
-                        screen_tile_id,
+                        context,
+                        &(level_tile_id % level_width, level_tile_id / level_width),
                         RendererColor::White,
                     );
                 }
             }
         }
-        if let Some(text) = &context.saved_level_name {
-            let (x, y) = get_bottom_text_position(&context.font, context.graphics.resolution_y);
-            context.font.render_text(renderer, text, (x, y));
-        }
-    }
-
-    fn render_level<L: LevelLister, R: Renderer>(
-        &self,
-        renderer: &mut R,
-        context: &Context<L, R::Texture>,
-    ) {
-        let level = &context.level;
-        let graphics = &context.graphics;
-        let textures = &context.textures;
-        let trigonometry = &context.trigonometry;
-        let render_size = context.graphics.get_render_size();
-
-        for y in 0..std::cmp::min(level.tiles.len() as u32, graphics.get_y_tiles_per_screen()) {
-            for x in 0..std::cmp::min(
-                level.tiles[y as usize].len() as u32,
-                graphics.get_x_tiles_per_screen(),
-            ) {
-                let (x_index, y_index) = get_scroll_corrected_indexes(level.scroll, x, y);
-                if y_index >= level.tiles.len() || x_index >= level.tiles[y_index].len() {
-                    continue;
-                }
-                let texture = match level.tiles[y_index][x_index].texture_type {
-                    TextureType::Floor => &textures.floor,
-                    TextureType::Walls => &textures.walls,
-                    TextureType::Shadow => unreachable!(),
-                };
-                let (texture_width, _) = texture.size();
-                let src = get_block(level.tiles[y_index][x_index].id, texture_width);
-                let (x_absolute, y_absolute) =
-                    get_absolute_coordinates_from_logical(x, y, graphics.get_render_size());
-                let dst = Rect::new(x_absolute, y_absolute, render_size, render_size);
-                renderer.render_texture(texture, Some(src), dst);
-                let (shadow_texture_width, _) = textures.shadows.size();
-                if level.tiles[y_index][x_index].shadow > 0 {
-                    let src = get_block(
-                        level.tiles[y_index][x_index].shadow - 1,
-                        shadow_texture_width,
-                    );
-                    renderer.render_texture(&textures.shadows, Some(src), dst);
-                }
-            }
-        }
-        for (coordinates, spotlight) in &level.spotlights {
-            let center =
-                get_screen_coordinates_from_level_coordinates(graphics, coordinates, &level.scroll);
-            renderer.draw_circle(
-                center,
-                get_spotlight_render_radius(spotlight),
-                RendererColor::Blue,
-            );
-        }
-        for (coordinates, steam) in &level.steams {
-            let center =
-                get_screen_coordinates_from_level_coordinates(graphics, coordinates, &level.scroll);
-            for x in 0..6 {
-                let multiplier = x as f32 * 6.0 * steam.range as f32;
-                renderer.draw_circle(
-                    Point::new(
-                        center.x + (trigonometry.sin[steam.angle as usize] * multiplier) as i32,
-                        center.y + (trigonometry.cos[steam.angle as usize] * multiplier) as i32,
-                    ),
-                    get_steam_render_radius() + x * 2,
-                    RendererColor::Red,
-                );
-            }
-        }
-
-        for (coordinates, crate_item) in &level.crates.staticc {
-            let box_size = get_crate_render_size();
-            let pos =
-                get_screen_coordinates_from_level_coordinates(graphics, coordinates, &level.scroll);
-            let color = match crate_item.crate_variant {
-                StaticCrate::Normal => RendererColor::LightGreen,
-                StaticCrate::Deathmatch => RendererColor::LightBlue,
-            };
-            renderer.draw_rect(&Rect::new(pos.x, pos.y, box_size, box_size), color);
-            renderer.draw_rect(
-                &Rect::new(pos.x + 1, pos.y + 1, box_size - 2, box_size - 2),
-                color,
-            );
-
-            let text = match crate_item.crate_class {
-                CrateClass::Weapon => weapon_crates(),
-                CrateClass::Bullet => bullet_crates(),
-                CrateClass::Energy => energy_crates(),
-            }[crate_item.crate_type as usize];
-            let (_, height) = context.font.text_size(text);
-            context.font.render_text(
-                renderer,
-                text,
-                ((pos.x - 10) as u32, (pos.y - 9 - height as i32) as u32),
-            );
-        }
     }
 
     fn render_input_prompt<L: LevelLister, R: Renderer>(
@@ -837,6 +898,30 @@ impl<W: LevelWriter> EditorState<W> {
                     };
                     "save level?"
                 }
+                PromptType::ReplaceTiles(ReplaceTilesState::SelectArea) => return,
+                PromptType::ReplaceTiles(ReplaceTilesState::Prompt) => {
+                    self.render_replace_tiles_info(
+                        renderer,
+                        context,
+                        prompt_position,
+                        prompt_line_spacing,
+                    );
+                    "replace tiles?"
+                }
+                PromptType::ClearShadowLocks => "clear manual shadows?",
+                PromptType::SaveFailed(file_name) => {
+                    context.font.render_text(
+                        renderer,
+                        &format!("could not save {}", file_name),
+                        prompt_position,
+                    );
+                    context.font.render_text(
+                        renderer,
+                        "press Y to save with another name",
+                        (prompt_position.0, prompt_position.1 + prompt_line_spacing),
+                    );
+                    return;
+                }
                 PromptType::Quit => "really wanna quit?",
                 PromptType::CreateShadows(shadow_state) => match shadow_state {
                     ShadowPromptType::Enabled => "disable auto shadow?",
@@ -855,91 +940,281 @@ impl<W: LevelWriter> EditorState<W> {
         }
     }
 
+    fn get_tool_name(&self) -> &'static str {
+        match self.insert_item {
+            InsertType::Spotlight(InsertState::Place) => "place spotlights",
+            InsertType::Spotlight(InsertState::Delete) => "delete spotlights",
+            InsertType::Steam(InsertState::Place) => "place steams",
+            InsertType::Steam(InsertState::Delete) => "delete steams",
+            InsertType::NormalCrate(InsertState::Place) => "place normal crates",
+            InsertType::DMCrate(InsertState::Place) => "place deathmatch crates",
+            InsertType::NormalCrate(InsertState::Delete)
+            | InsertType::DMCrate(InsertState::Delete) => "delete crates",
+            InsertType::SelectObjects => "select objects",
+            InsertType::None => match self.prompt {
+                PromptType::ReplaceTiles(_) => "replace tiles",
+                _ => "draw tiles",
+            },
+        }
+    }
+
+    fn get_hovered_object<L: LevelLister, T: Texture>(
+        &self,
+        context: &Context<L, T>,
+    ) -> Option<LevelObject> {
+        if let Some((object, _)) = self.drag_object {
+            return Some(object);
+        }
+        if self.insert_item != InsertType::None || self.drag_tiles {
+            return None;
+        }
+        context.level.find_object(
+            &get_level_coordinates_from_screen_coordinates(
+                &context.graphics,
+                &context.mouse,
+                &context.viewport,
+            ),
+            context.graphics.zoom,
+        )
+    }
+
+    fn render_replace_tiles_info<L: LevelLister, R: Renderer>(
+        &self,
+        renderer: &mut R,
+        context: &Context<L, R::Texture>,
+        prompt_position: (u32, u32),
+        prompt_line_spacing: u32,
+    ) {
+        let tile_replace = self.tile_replace.as_ref().unwrap();
+        let tile_match = tile_replace.tile_match();
+        let lines = [
+            format!(
+                "{} -> {}",
+                get_tile_text(tile_match.texture_type, tile_match.id),
+                get_tile_text(tile_replace.replacement.0, tile_replace.replacement.1),
+            ),
+            match tile_match.shadow {
+                Some(shadow) => format!("S - match shadow: {}", shadow),
+                None => "S - match shadow: any".to_string(),
+            },
+            match tile_replace.area {
+                Some(((x0, y0), (x1, y1))) => {
+                    format!("A - area: {},{} - {},{}", x0, y0, x1, y1)
+                }
+                None => "A - area: whole level".to_string(),
+            },
+            format!(
+                "{} tiles will be replaced",
+                context
+                    .level
+                    .find_tiles(&tile_match, tile_replace.area)
+                    .len()
+            ),
+        ];
+        for (index, line) in lines.iter().enumerate() {
+            context.font.render_text(
+                renderer,
+                line,
+                (
+                    prompt_position.0,
+                    prompt_position.1 + (index as u32 + 2) * prompt_line_spacing,
+                ),
+            );
+        }
+    }
+
+    fn save_level<L: LevelLister, T: Texture, I: TextInput>(
+        &mut self,
+        context: &mut Context<L, T>,
+        text_input: &mut I,
+    ) {
+        let level_save_name_uppercase = context.level_save_name.to_uppercase();
+        let level_saved_name = format!("{}.LEV", &level_save_name_uppercase);
+        let level_data = context.level.serialize();
+        let mut result = W::write(&level_saved_name, &level_data);
+        if result.is_ok() {
+            // A sidecar left from an earlier save would bring back locks which have been cleared
+            let sidecar_name = format!("{}.{}", &level_save_name_uppercase, SHADOW_LOCK_EXTENSION);
+            result = if context.level.shadow_locks.is_empty() {
+                W::remove(&sidecar_name)
+            } else {
+                W::write(&sidecar_name, &context.level.serialize_shadow_locks())
+            };
+        }
+        text_input.stop();
+        if result.is_err() {
+            self.prompt = PromptType::SaveFailed(level_saved_name);
+            return;
+        }
+        context.saved_level_name = Some(level_saved_name.to_lowercase());
+        context.unsaved_changes = false;
+        self.prompt = PromptType::None;
+    }
+
     fn handle_mouse_left_down<L: LevelLister, T: Texture>(&mut self, context: &mut Context<L, T>) {
         if self.drag_tiles {
             return;
         }
 
-        if self.set_position > 0 {
-            let position = if self.set_position == 1 {
-                &mut context.level.p1_position
-            } else {
-                &mut context.level.p2_position
+        let level_coordinates = get_level_coordinates_from_screen_coordinates(
+            &context.graphics,
+            &context.mouse,
+            &context.viewport,
+        );
+        if let Some((object, offset)) = self.drag_object {
+            let level_coordinates = match self.drag_axis_origin {
+                Some(origin) => constrain_to_axis(&origin, &level_coordinates),
+                None => level_coordinates,
             };
-            *position = get_logical_coordinates(
-                &context.graphics,
-                context.mouse.0,
-                context.mouse.1,
-                Some(context.level.scroll),
-            );
-            self.set_position = 0;
-        } else {
-            let level_coordinates = get_level_coordinates_from_screen_coordinates(
-                &context.graphics,
-                &context.mouse,
-                &context.level.scroll,
+            let position = (
+                std::cmp::max(level_coordinates.0 as i64 - offset.0, 0) as u32,
+                std::cmp::max(level_coordinates.1 as i64 - offset.1, 0) as u32,
             );
-            match self.insert_item {
-                InsertType::Spotlight(InsertState::Place) => {
-                    self.insert_item =
-                        InsertType::Spotlight(InsertState::Instructions(level_coordinates));
-                    context.level.put_spotlight_to_level(&level_coordinates, 0);
-                }
-                InsertType::Spotlight(InsertState::Delete) => {
-                    context.level.delete_spotlight_if_near(
-                        &level_coordinates,
-                        context.graphics.render_multiplier,
-                    );
-                }
-                InsertType::Steam(InsertState::Place) => {
-                    self.insert_item =
-                        InsertType::Steam(InsertState::Instructions(level_coordinates));
-                    context
-                        .level
-                        .put_steam_to_level(&level_coordinates, &Steam { angle: 0, range: 1 });
-                }
-                InsertType::Steam(InsertState::Delete) => {
-                    context.level.delete_steam_if_near(
-                        &level_coordinates,
-                        context.graphics.render_multiplier,
-                    );
-                }
-                InsertType::NormalCrate(InsertState::Place) => {
-                    self.insert_item =
-                        InsertType::NormalCrate(InsertState::Instructions(level_coordinates));
-                    context.level.put_crate_to_level(
-                        &level_coordinates,
-                        &StaticCrateType {
-                            crate_variant: StaticCrate::Normal,
-                            crate_class: CrateClass::Weapon,
-                            crate_type: 0,
-                        },
-                    );
-                }
-                InsertType::DMCrate(InsertState::Place) => {
-                    self.insert_item =
-                        InsertType::DMCrate(InsertState::Instructions(level_coordinates));
-                    context.level.put_crate_to_level(
-                        &level_coordinates,
-                        &StaticCrateType {
-                            crate_variant: StaticCrate::Deathmatch,
-                            crate_class: CrateClass::Weapon,
-                            crate_type: 0,
-                        },
-                    );
+            let current_position = context.level.get_object_position(&object);
+            if position != current_position {
+                context.unsaved_changes = true;
+            }
+            let object = if self.selection.len() > 1 && self.selection.contains(&object) {
+                let index = self.selection.iter().position(|other| *other == object);
+                self.selection = context.level.move_objects(
+                    &self.selection,
+                    (
+                        position.0 as i64 - current_position.0 as i64,
+                        position.1 as i64 - current_position.1 as i64,
+                    ),
+                );
+                self.selection[index.unwrap()]
+            } else {
+                let object = context.level.move_object(&object, &position);
+                if !self.selection.is_empty() {
+                    self.selection = vec![object];
                 }
-                InsertType::NormalCrate(InsertState::Delete) => {
-                    context.level.delete_crate_if_near(
-                        &level_coordinates,
-                        context.graphics.render_multiplier,
-                    );
+                object
+            };
+            self.drag_object = Some((object, offset));
+            return;
+        }
+        if let Some(coordinates) = self.aimed_steam {
+            let steam = get_aimed_steam(context, &coordinates);
+            context.level.put_steam_to_level(&coordinates, &steam);
+            context.unsaved_changes = true;
+            return;
+        }
+        if !matches!(
+            self.insert_item,
+            InsertType::None | InsertType::SelectObjects
+        ) {
+            context.unsaved_changes = true;
+        }
+        match self.insert_item {
+            InsertType::Spotlight(InsertState::Place) => {
+                context.level.put_spotlight_to_level(&level_coordinates, 0);
+                self.place_object(LevelObject::Spotlight(level_coordinates));
+            }
+            InsertType::Spotlight(InsertState::Delete) => {
+                context
+                    .level
+                    .delete_spotlight_if_near(&level_coordinates, context.graphics.zoom);
+            }
+            InsertType::Steam(InsertState::Place) => {
+                let coordinates = match context
+                    .level
+                    .find_object(&level_coordinates, context.graphics.zoom)
+                {
+                    Some(LevelObject::Steam(coordinates)) => coordinates,
+                    _ => {
+                        context
+                            .level
+                            .put_steam_to_level(&level_coordinates, &Steam { angle: 0, range: 1 });
+                        level_coordinates
+                    }
+                };
+                self.select_object(Some(LevelObject::Steam(coordinates)));
+                self.aimed_steam = Some(coordinates);
+            }
+            InsertType::Steam(InsertState::Delete) => {
+                context
+                    .level
+                    .delete_steam_if_near(&level_coordinates, context.graphics.zoom);
+            }
+            InsertType::NormalCrate(InsertState::Place) => {
+                context.level.put_crate_to_level(
+                    &level_coordinates,
+                    &StaticCrateType {
+                        crate_variant: StaticCrate::Normal,
+                        crate_class: CrateClass::Weapon,
+                        crate_type: 0,
+                    },
+                );
+                self.place_object(LevelObject::Crate(level_coordinates));
+            }
+            InsertType::DMCrate(InsertState::Place) => {
+                context.level.put_crate_to_level(
+                    &level_coordinates,
+                    &StaticCrateType {
+                        crate_variant: StaticCrate::Deathmatch,
+                        crate_class: CrateClass::Weapon,
+                        crate_type: 0,
+                    },
+                );
+                self.place_object(LevelObject::Crate(level_coordinates));
+            }
+            InsertType::NormalCrate(InsertState::Delete) => {
+                context
+                    .level
+                    .delete_crate_if_near(&level_coordinates, context.graphics.zoom);
+            }
+            InsertType::None => {
+                let object =
+                    if self.prompt == PromptType::ReplaceTiles(ReplaceTilesState::SelectArea) {
+                        None
+                    } else {
+                        context
+                            .level
+                            .find_object(&level_coordinates, context.graphics.zoom)
+                    };
+                if !object.is_some_and(|object| self.selection.contains(&object)) {
+                    self.select_object(object);
                 }
-                InsertType::None => {
-                    self.drag_tiles = true;
+                match object {
+                    Some(object) => {
+                        let position = context.level.get_object_position(&object);
+                        self.drag_object = Some((
+                            object,
+                            (
+                                level_coordinates.0 as i64 - position.0 as i64,
+                                level_coordinates.1 as i64 - position.1 as i64,
+                            ),
+                        ));
+                    }
+                    None => self.drag_tiles = true,
                 }
-                _ => {}
-            };
+            }
+            _ => {}
+        };
+    }
+
+    // Selects the object for the inspector, player starts have no editable properties
+    fn select_object(&mut self, object: Option<LevelObject>) {
+        self.set_selection(
+            object
+                .into_iter()
+                .filter(|object| !matches!(object, LevelObject::PlayerStart(_)))
+                .collect(),
+        );
+    }
+
+    fn set_selection(&mut self, selection: Vec<LevelObject>) {
+        if selection != self.selection {
+            self.inspector.reset();
         }
+        self.selection = selection;
+    }
+
+    // Newly placed object is selected and follows the mouse until the button is released
+    fn place_object(&mut self, object: LevelObject) {
+        self.select_object(Some(object));
+        self.drag_object = Some((object, (0, 0)));
     }
 
     fn handle_mouse_right_down<L: LevelLister, T: Texture>(&self, context: &mut Context<L, T>) {
@@ -949,15 +1224,159 @@ impl<W: LevelWriter> EditorState<W> {
                 &context.graphics,
                 &context.mouse,
                 &context.level,
-                context.graphics.get_render_size(),
+                &context.viewport,
             ),
             context.level.tiles[0].len() as u32,
-            Some(context.level.scroll),
+            Some(context.viewport),
         );
         context
             .level
             .put_tile_to_level(pointed_tile, None, &TextureType::Shadow);
-        context.automatic_shadows = false;
+        context.unsaved_changes = true;
+    }
+}
+
+// Draws the level layers without any editor UI. Crates in labeled_objects get their name drawn
+// above them.
+pub fn render_level<L: LevelLister, R: Renderer>(
+    renderer: &mut R,
+    context: &Context<L, R::Texture>,
+    layers: &Layers,
+    steam_time: u32,
+    labeled_objects: &[LevelObject],
+) {
+    let level = &context.level;
+    let graphics = &context.graphics;
+    let textures = &context.textures;
+    let trigonometry = &context.trigonometry;
+    let viewport = &context.viewport;
+    let render_size = context.graphics.get_render_size();
+
+    // One extra row and column for the tiles partially visible at both edges
+    let first_tile = (viewport.0 / TILE_SIZE, viewport.1 / TILE_SIZE);
+    for y in first_tile.1..first_tile.1 + graphics.get_y_tiles_per_screen() + 1 {
+        for x in first_tile.0..first_tile.0 + graphics.get_x_tiles_per_screen() + 1 {
+            let (x_index, y_index) = (x as usize, y as usize);
+            if y_index >= level.tiles.len() || x_index >= level.tiles[y_index].len() {
+                continue;
+            }
+            let texture_type = level.tiles[y_index][x_index].texture_type;
+            let texture = match texture_type {
+                TextureType::Floor => &textures.floor,
+                TextureType::Walls => &textures.walls,
+                TextureType::Shadow => unreachable!(),
+            };
+            let (texture_width, _) = texture.size();
+            let src = get_block(level.tiles[y_index][x_index].id, texture_width);
+            let position = get_screen_coordinates_from_level_coordinates(
+                graphics,
+                &(x * TILE_SIZE, y * TILE_SIZE),
+                viewport,
+            );
+            let dst = Rect::new(position.x, position.y, render_size, render_size);
+            if layers.shows_texture(texture_type) {
+                renderer.render_texture(texture, Some(src), dst);
+            }
+            let (shadow_texture_width, _) = textures.shadows.size();
+            if layers.shadows && level.tiles[y_index][x_index].shadow > 0 {
+                let src = get_block(
+                    level.tiles[y_index][x_index].shadow - 1,
+                    shadow_texture_width,
+                );
+                renderer.render_texture(&textures.shadows, Some(src), dst);
+            }
+        }
+    }
+    if layers.lighting {
+        render_lighting(renderer, context);
+    }
+    if layers.grid {
+        render_grid(renderer, context);
+    }
+    if layers.spotlights {
+        for (coordinates, spotlight) in &level.spotlights {
+            let center =
+                get_screen_coordinates_from_level_coordinates(graphics, coordinates, viewport);
+            renderer.draw_circle(
+                center,
+                get_spotlight_render_radius(spotlight),
+                RendererColor::Blue,
+            );
+        }
+    }
+    if layers.steams {
+        for (coordinates, steam) in &level.steams {
+            let center =
+                get_screen_coordinates_from_level_coordinates(graphics, coordinates, viewport);
+            // Spaced in level pixels like the aiming and the particles, so they scale with zoom
+            let step = steam.range as f32 * STEAM_RANGE_STEP / (STEAM_RENDER_CIRCLES - 1) as f32
+                * graphics.zoom as f32
+                / 100.0;
+            for x in 0..STEAM_RENDER_CIRCLES {
+                let multiplier = x as f32 * step;
+                renderer.draw_circle(
+                    Point::new(
+                        center.x + (trigonometry.sin[steam.angle as usize] * multiplier) as i32,
+                        center.y + (trigonometry.cos[steam.angle as usize] * multiplier) as i32,
+                    ),
+                    get_steam_render_radius() + x * 2,
+                    RendererColor::Red,
+                );
+            }
+            if layers.steam_animation {
+                render_steam_particles(renderer, context, coordinates, steam, steam_time);
+            }
+        }
+    }
+
+    for (coordinates, crate_item) in &level.crates.staticc {
+        if !layers.shows_crate(crate_item.crate_variant) {
+            continue;
+        }
+        let box_size = get_crate_render_size();
+        let pos = get_screen_coordinates_from_level_coordinates(graphics, coordinates, viewport);
+        let color = match crate_item.crate_variant {
+            StaticCrate::Normal => RendererColor::LightGreen,
+            StaticCrate::Deathmatch => RendererColor::LightBlue,
+        };
+        renderer.draw_rect(&Rect::new(pos.x, pos.y, box_size, box_size), color);
+        renderer.render_texture(
+            &textures.crates,
+            Some(get_crate_icon_rect(crate_item)),
+            Rect::new(
+                pos.x + ((box_size - CRATE_ICON_SIZE) / 2) as i32,
+                pos.y + ((box_size - CRATE_ICON_SIZE) / 2) as i32,
+                CRATE_ICON_SIZE,
+                CRATE_ICON_SIZE,
+            ),
+        );
+
+        // Labels would overlap each other, so only show the ones asked for
+        if !labeled_objects.contains(&LevelObject::Crate(*coordinates)) {
+            continue;
+        }
+        let text = crates(crate_item.crate_class)[crate_item.crate_type as usize];
+        let (_, height) = context.font.text_size(text);
+        context.font.render_text(
+            renderer,
+            text,
+            ((pos.x - 10) as u32, (pos.y - 9 - height as i32) as u32),
+        );
+    }
+    if layers.player_labels {
+        for (text, position) in [
+            ("PL1", context.level.p1_position),
+            ("PL2", context.level.p2_position),
+        ] {
+            let origo = get_screen_coordinates_from_level_coordinates(
+                &context.graphics,
+                &(position.0 * TILE_SIZE, position.1 * TILE_SIZE),
+                &context.viewport,
+            );
+            context
+                .font
+                .render_text_relative(renderer, text, (origo.x, origo.y), (0, 0));
+        }
     }
 }
 
@@ -977,23 +1396,302 @@ fn get_limited_screen_level_size(
     graphics: &Graphics,
     mouse: &(u32, u32),
     level: &Level,
-    render_size: u32,
+    viewport: &(u32, u32),
 ) -> (u32, u32) {
+    let level_end = get_screen_coordinates_from_level_coordinates(
+        graphics,
+        &(
+            level.tiles[0].len() as u32 * TILE_SIZE,
+            level.tiles.len() as u32 * TILE_SIZE,
+        ),
+        viewport,
+    );
     limit_coordinates(
         &(
-            std::cmp::min(
-                mouse.0,
-                (level.tiles[0].len() as u32 - level.scroll.0) * render_size - 1,
-            ),
-            std::cmp::min(
-                mouse.1,
-                (level.tiles.len() as u32 - level.scroll.1) * render_size - 1,
-            ),
+            std::cmp::min(mouse.0, std::cmp::max(level_end.x - 1, 0) as u32),
+            std::cmp::min(mouse.1, std::cmp::max(level_end.y - 1, 0) as u32),
         ),
         &(graphics.resolution_x, graphics.resolution_y),
     )
 }
 
+// Particles flying from the steam position along its angle up to its range, in level pixels
+// so that the coverage can be compared to the walls
+fn render_steam_particles<L: LevelLister, R: Renderer>(
+    renderer: &mut R,
+    context: &Context<L, R::Texture>,
+    coordinates: &(u32, u32),
+    steam: &Steam,
+    time: u32,
+) {
+    let trigonometry = &context.trigonometry;
+    let zoom = context.graphics.zoom as f32 / 100.0;
+    let center = get_screen_coordinates_from_level_coordinates(
+        &context.graphics,
+        coordinates,
+        &context.viewport,
+    );
+    let direction = (
+        trigonometry.sin[steam.angle as usize],
+        trigonometry.cos[steam.angle as usize],
+    );
+    for particle in 0..STEAM_PARTICLES {
+        let age = time.wrapping_add(particle * STEAM_PARTICLE_LIFETIME_MS / STEAM_PARTICLES)
+            % STEAM_PARTICLE_LIFETIME_MS;
+        let progress = age as f32 / STEAM_PARTICLE_LIFETIME_MS as f32;
+        let distance = progress * steam.range as f32 * STEAM_RANGE_STEP;
+        // Fixed sideways drift per particle spreads the particles into a cone
+        let drift = distance * 0.2 * trigonometry.sin[(particle * 137 % 360) as usize];
+        let x = direction.0 * distance + direction.1 * drift;
+        let y = direction.1 * distance - direction.0 * drift;
+        renderer.draw_circle(
+            Point::new(center.x + (x * zoom) as i32, center.y + (y * zoom) as i32),
+            std::cmp::max(((1.0 + progress * 4.0) * zoom) as u32, 1),
+            RendererColor::LightGrey,
+        );
+    }
+}
+
+fn render_lighting<L: LevelLister, R: Renderer>(
+    renderer: &mut R,
+    context: &Context<L, R::Texture>,
+) {
+    let graphics = &context.graphics;
+    let level = &context.level;
+    let top_left =
+        get_screen_coordinates_from_level_coordinates(graphics, &(0, 0), &context.viewport);
+    let bottom_right = get_screen_coordinates_from_level_coordinates(
+        graphics,
+        &(
+            level.tiles[0].len() as u32 * TILE_SIZE,
+            level.tiles.len() as u32 * TILE_SIZE,
+        ),
+        &context.viewport,
+    );
+    let start = (top_left.x.max(0), top_left.y.max(0));
+    let end = (
+        bottom_right.x.min(graphics.resolution_x as i32),
+        bottom_right.y.min(graphics.resolution_y as i32),
+    );
+    renderer.fill_rect_blended(
+        &Rect::new(
+            start.0,
+            start.1,
+            (end.0 - start.0) as u32,
+            (end.1 - start.1) as u32,
+        ),
+        Color::from((0, 0, 0, AMBIENT_DARKNESS)),
+    );
+    for (coordinates, spotlight) in &level.spotlights {
+        let center =
+            get_screen_coordinates_from_level_coordinates(graphics, coordinates, &context.viewport);
+        let radius = (get_spotlight_light_radius(spotlight) * graphics.zoom / 100) as i32;
+        renderer.render_texture_additive(
+            &context.textures.light,
+            Rect::new(
+                center.x - radius,
+                center.y - radius,
+                radius as u32 * 2,
+                radius as u32 * 2,
+            ),
+            // Loaded levels can have intensities above 9, which would not fit in the strength
+            (LIGHT_BASE_STRENGTH + *spotlight as u32 * LIGHT_STRENGTH_STEP).min(255) as u8,
+        );
+    }
+}
+
+fn render_grid<L: LevelLister, R: Renderer>(renderer: &mut R, context: &Context<L, R::Texture>) {
+    let level_size = (
+        context.level.tiles[0].len() as u32 * TILE_SIZE,
+        context.level.tiles.len() as u32 * TILE_SIZE,
+    );
+    let top_left = get_screen_coordinates_from_level_coordinates(
+        &context.graphics,
+        &(0, 0),
+        &context.viewport,
+    );
+    let bottom_right = get_screen_coordinates_from_level_coordinates(
+        &context.graphics,
+        &level_size,
+        &context.viewport,
+    );
+    // Lines are limited to the screen, grid outside of it is skipped
+    let graphics = &context.graphics;
+    let render_size = graphics.get_render_size() as usize;
+    let start = (top_left.x.max(0), top_left.y.max(0));
+    let end = (
+        bottom_right.x.min(graphics.resolution_x as i32),
+        bottom_right.y.min(graphics.resolution_y as i32),
+    );
+    for x in (top_left.x..=bottom_right.x).step_by(render_size) {
+        if x >= start.0 && x < end.0 {
+            renderer.fill_rect(
+                &Rect::new(x, start.1, 1, (end.1 - start.1) as u32),
+                RendererColor::DarkGrey,
+            );
+        }
+    }
+    for y in (top_left.y..=bottom_right.y).step_by(render_size) {
+        if y >= start.1 && y < end.1 {
+            renderer.fill_rect(
+                &Rect::new(start.0, y, (end.0 - start.0) as u32, 1),
+                RendererColor::DarkGrey,
+            );
+        }
+    }
+}
+
+fn highlight_level_tile<L: LevelLister, R: Renderer>(
+    renderer: &mut R,
+    context: &Context<L, R::Texture>,
+    tile: &(u32, u32),
+    color: RendererColor,
+) {
+    let render_size = context.graphics.get_render_size();
+    let position = get_screen_coordinates_from_level_coordinates(
+        &context.graphics,
+        &(tile.0 * TILE_SIZE, tile.1 * TILE_SIZE),
+        &context.viewport,
+    );
+    renderer.draw_rect(
+        &Rect::new(position.x, position.y, render_size, render_size),
+        color,
+    );
+}
+
+// Steam pointing from its position towards the mouse with the range reaching the mouse, as
+// drawn by the steam render
+fn get_aimed_steam<L: LevelLister, T: Texture>(
+    context: &Context<L, T>,
+    coordinates: &(u32, u32),
+) -> Steam {
+    let steam = context.level.get_steam_from_level(coordinates);
+    let center = get_screen_coordinates_from_level_coordinates(
+        &context.graphics,
+        coordinates,
+        &context.viewport,
+    );
+    let dx = context.mouse.0 as f32 - center.x as f32;
+    let dy = context.mouse.1 as f32 - center.y as f32;
+    if dx == 0.0 && dy == 0.0 {
+        return steam;
+    }
+    let trigonometry = &context.trigonometry;
+    let alignment = |angle: &usize| trigonometry.sin[*angle] * dx + trigonometry.cos[*angle] * dy;
+    let angle = (0..360)
+        .step_by(5)
+        .max_by(|a, b| alignment(a).total_cmp(&alignment(b)))
+        .unwrap();
+    // The range step is in level pixels, the mouse distance in screen pixels
+    let distance = (dx * dx + dy * dy).sqrt() * 100.0 / context.graphics.zoom as f32;
+    let range = (distance / STEAM_RANGE_STEP).round();
+    Steam {
+        angle: angle as u16,
+        range: range.min(6.0) as u8,
+    }
+}
+
+// Keeps the larger of horizontal and vertical movement from the origin
+fn constrain_to_axis(origin: &(u32, u32), coordinates: &(u32, u32)) -> (u32, u32) {
+    if origin.0.abs_diff(coordinates.0) >= origin.1.abs_diff(coordinates.1) {
+        (coordinates.0, origin.1)
+    } else {
+        (origin.0, coordinates.1)
+    }
+}
+
+// Scrolls by the given amount of level pixels, limited so that the level fills the screen
+fn scroll_level<L: LevelLister, T: Texture>(context: &mut Context<L, T>, pixels: (i64, i64)) {
+    let view_size = status_bar::get_level_view_size(&context.graphics, &context.font);
+    let max_viewport = (
+        (context.level.tiles[0].len() as u32 * TILE_SIZE) as i64 - view_size.0 as i64,
+        (context.level.tiles.len() as u32 * TILE_SIZE) as i64 - view_size.1 as i64,
+    );
+    let viewport = &mut context.viewport;
+    viewport.0 = (viewport.0 as i64 + pixels.0).clamp(0, std::cmp::max(max_viewport.0, 0)) as u32;
+    viewport.1 = (viewport.1 as i64 + pixels.1).clamp(0, std::cmp::max(max_viewport.1, 0)) as u32;
+}
+
+fn center_viewport<L: LevelLister, T: Texture>(context: &mut Context<L, T>, center: &(u32, u32)) {
+    let view_size = status_bar::get_level_view_size(&context.graphics, &context.font);
+    context.viewport = (0, 0);
+    scroll_level(
+        context,
+        (
+            center.0 as i64 - view_size.0 as i64 / 2,
+            center.1 as i64 - view_size.1 as i64 / 2,
+        ),
+    );
+}
+
+// Zooms while keeping the level point under the cursor in place
+fn zoom_level<L: LevelLister, T: Texture>(context: &mut Context<L, T>, zoom_in: bool) {
+    let anchor = get_level_coordinates_from_screen_coordinates(
+        &context.graphics,
+        &context.mouse,
+        &context.viewport,
+    );
+    if zoom_in {
+        context.graphics.zoom_in();
+    } else {
+        context.graphics.zoom_out();
+    }
+    let mouse_offset =
+        get_level_coordinates_from_screen_coordinates(&context.graphics, &context.mouse, &(0, 0));
+    context.viewport = (0, 0);
+    scroll_level(
+        context,
+        (
+            anchor.0 as i64 - mouse_offset.0 as i64,
+            anchor.1 as i64 - mouse_offset.1 as i64,
+        ),
+    );
+}
+
+fn render_object_highlight<L: LevelLister, R: Renderer>(
+    renderer: &mut R,
+    context: &Context<L, R::Texture>,
+    object: &LevelObject,
+) {
+    let level = &context.level;
+    let position = get_screen_coordinates_from_level_coordinates(
+        &context.graphics,
+        &level.get_object_position(object),
+        &context.viewport,
+    );
+    match object {
+        LevelObject::Spotlight(coordinates) => renderer.draw_circle(
+            position,
+            get_spotlight_render_radius(&level.spotlights[coordinates]) + 2,
+            RendererColor::White,
+        ),
+        LevelObject::Steam(_) => renderer.draw_circle(
+            position,
+            get_steam_render_radius() + 2,
+            RendererColor::White,
+        ),
+        LevelObject::Crate(_) => {
+            let box_size = get_crate_render_size();
+            renderer.draw_rect(
+                &Rect::new(position.x - 2, position.y - 2, box_size + 4, box_size + 4),
+                RendererColor::White,
+            );
+        }
+        LevelObject::PlayerStart(_) => {
+            let render_size = context.graphics.get_render_size();
+            renderer.draw_rect(
+                &Rect::new(
+                    position.x + 1,
+                    position.y + 1,
+                    render_size - 2,
+                    render_size - 2,
+                ),
+                RendererColor::White,
+            );
+        }
+    }
+}
+
 fn get_block(id: u32, width: u32) -> Rect {
     let (x, y) = get_tile_coordinates(id, width);
     Rect::new(x as i32, y as i32, TILE_SIZE, TILE_SIZE)
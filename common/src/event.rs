@@ -1,12 +1,56 @@
 pub enum Event {
     Quit,
-    Window { win_event: WindowEvent },
-    KeyDown { keycode: Keycode },
-    MouseButtonDown { button: MouseButton },
-    MouseButtonUp { button: MouseButton },
-    MouseMotion { x: u32, y: u32 },
-    TextInput { text: String },
-    FileUpload { name: String, data: Vec<u8> },
+    Window {
+        win_event: WindowEvent,
+    },
+    KeyDown {
+        keycode: Keycode,
+        modifiers: Modifiers,
+    },
+    MouseButtonDown {
+        button: MouseButton,
+        modifiers: Modifiers,
+    },
+    MouseButtonUp {
+        button: MouseButton,
+    },
+    MouseMotion {
+        x: u32,
+        y: u32,
+    },
+    MouseWheel {
+        x: i32,
+        y: i32,
+        modifiers: Modifiers,
+    }, // positive y away from the user
+    TextInput {
+        text: String,
+    },
+    FileUpload {
+        name: String,
+        data: Vec<u8>,
+    },
+    Tick {
+        milliseconds: u32, // time since the previous tick
+    },
+}
+
+// Frontends send Event::Tick at about this interval for animations
+pub const TICK_INTERVAL_MS: u32 = 33;
+
+// Wheel steps in list-like views behave as arrow key presses
+pub fn wheel_to_key_down(y: i32) -> Event {
+    Event::KeyDown {
+        keycode: if y > 0 { Keycode::Up } else { Keycode::Down },
+        modifiers: Modifiers::default(),
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool, // also Cmd on macOS in the web frontend
+    pub alt: bool,
 }
 
 #[derive(Clone, Copy)]
@@ -17,15 +61,21 @@ pub enum WindowEvent {
 
 pub enum MouseButton {
     Left,
+    Middle,
     Right,
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Keycode {
     Escape,
     Backspace,
+    Tab,
     Return,
     Space,
+    Delete,
+    Insert,
+    Home,
+    End,
     PageDown,
     PageUp,
     Up,
@@ -38,21 +88,123 @@ pub enum Keycode {
     Minus,
     Plus,
     A,
+    B,
     C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
     Q,
+    R,
     S,
+    T,
+    U,
+    V,
     W,
     X,
     Y,
     Z,
+    Num0,
     Num1,
     Num2,
+    Num3,
+    Num4,
+    Num5,
+    Num6,
+    Num7,
+    Num8,
+    Num9,
     F1,
     F2,
     F3,
     F4,
+    F5,
     F6,
     F7,
     F8,
     F9,
+    F10,
+    F11,
+    F12,
 }
+
+// Every key, e.g. for parsing key names
+pub const ALL_KEYCODES: [Keycode; 68] = [
+    Keycode::Escape,
+    Keycode::Backspace,
+    Keycode::Tab,
+    Keycode::Return,
+    Keycode::Space,
+    Keycode::Delete,
+    Keycode::Insert,
+    Keycode::Home,
+    Keycode::End,
+    Keycode::PageDown,
+    Keycode::PageUp,
+    Keycode::Up,
+    Keycode::Down,
+    Keycode::Left,
+    Keycode::Right,
+    Keycode::KpEnter,
+    Keycode::KpMinus,
+    Keycode::KpPlus,
+    Keycode::Minus,
+    Keycode::Plus,
+    Keycode::A,
+    Keycode::B,
+    Keycode::C,
+    Keycode::D,
+    Keycode::E,
+    Keycode::F,
+    Keycode::G,
+    Keycode::H,
+    Keycode::I,
+    Keycode::J,
+    Keycode::K,
+    Keycode::L,
+    Keycode::M,
+    Keycode::N,
+    Keycode::O,
+    Keycode::P,
+    Keycode::Q,
+    Keycode::R,
+    Keycode::S,
+    Keycode::T,
+    Keycode::U,
+    Keycode::V,
+    Keycode::W,
+    Keycode::X,
+    Keycode::Y,
+    Keycode::Z,
+    Keycode::Num0,
+    Keycode::Num1,
+    Keycode::Num2,
+    Keycode::Num3,
+    Keycode::Num4,
+    Keycode::Num5,
+    Keycode::Num6,
+    Keycode::Num7,
+    Keycode::Num8,
+    Keycode::Num9,
+    Keycode::F1,
+    Keycode::F2,
+    Keycode::F3,
+    Keycode::F4,
+    Keycode::F5,
+    Keycode::F6,
+    Keycode::F7,
+    Keycode::F8,
+    Keycode::F9,
+    Keycode::F10,
+    Keycode::F11,
+    Keycode::F12,
+];
@@ -1,5 +1,5 @@
 use crate::context::Context;
-use crate::event::{Event, Keycode};
+use crate::event::{wheel_to_key_down, Event, Keycode};
 use crate::load_level::LevelLister;
 use crate::render::{Renderer, Texture};
 use crate::types::*;
@@ -111,6 +111,7 @@ impl GeneralLevelInfoState {
             Event::Quit { .. }
             | Event::KeyDown {
                 keycode: Keycode::Escape,
+                ..
             } => {
                 text_input.stop();
                 return EventResult::ChangeMode(Mode::Editor);
@@ -120,9 +121,13 @@ impl GeneralLevelInfoState {
             }
             Event::TextInput { text, .. } => {
                 if let Value::Comment = self.options[self.selected].value {
-                    sanitize_level_comment_input(&text, &mut context.level.general_info.comment)
+                    sanitize_level_comment_input(&text, &mut context.level.general_info.comment);
+                    context.unsaved_changes = true;
                 }
             }
+            Event::MouseWheel { y, .. } if y != 0 => {
+                return self.handle_event(context, text_input, wheel_to_key_down(y));
+            }
             Event::KeyDown { keycode, .. } => match keycode {
                 Keycode::Down => {
                     if self.selected < self.options.len() - 1 {
@@ -136,29 +141,36 @@ impl GeneralLevelInfoState {
                         self.enable_text_editing_if_needed(text_input);
                     }
                 }
-                Keycode::Right => match self.options[self.selected].value {
-                    Value::Number(index) => context.level.general_info.enemy_table[index] += 1,
-                    Value::TimeLimit => context.level.general_info.time_limit += 10,
-                    _ => return EventResult::EventIgnored,
-                },
+                Keycode::Right => {
+                    match self.options[self.selected].value {
+                        Value::Number(index) => context.level.general_info.enemy_table[index] += 1,
+                        Value::TimeLimit => context.level.general_info.time_limit += 10,
+                        _ => return EventResult::EventIgnored,
+                    }
+                    context.unsaved_changes = true;
+                }
                 Keycode::Left => match self.options[self.selected].value {
                     Value::Number(index) => {
                         let value = &mut context.level.general_info.enemy_table[index];
                         if *value > 0 {
                             *value -= 1;
+                            context.unsaved_changes = true;
                         }
                     }
                     Value::TimeLimit => {
                         let value = &mut context.level.general_info.time_limit;
                         if *value > 0 {
                             *value -= 10;
+                            context.unsaved_changes = true;
                         }
                     }
                     _ => return EventResult::EventIgnored,
                 },
                 Keycode::Backspace => {
                     if let Value::Comment = self.options[self.selected].value {
-                        context.level.general_info.comment.pop();
+                        if context.level.general_info.comment.pop().is_some() {
+                            context.unsaved_changes = true;
+                        }
                     }
                 }
                 _ => return EventResult::EventIgnored,
@@ -1,8 +1,12 @@
 use crate::level::TILE_SIZE;
 
+// Level view zoom levels in percent, chosen so that tiles are always whole pixels
+pub const ZOOM_LEVELS: [u32; 8] = [25, 50, 75, 100, 150, 200, 300, 400];
+
+#[derive(Clone, Copy)]
 pub struct Graphics {
-    pub render_multiplier: u32,
-    pub supports_scaling: bool,
+    pub render_multiplier: u32, // user interface scale, e.g. in tile selection
+    pub zoom: u32,              // level view scale in percent
     pub resolution_x: u32,
     pub resolution_y: u32,
 }
@@ -11,29 +15,41 @@ impl Graphics {
     pub fn new(resolution: (u32, u32), render_multiplier: u32) -> Graphics {
         Graphics {
             render_multiplier,
-            supports_scaling: render_multiplier > 1,
+            zoom: render_multiplier * 100,
             resolution_x: resolution.0,
             resolution_y: resolution.1,
         }
     }
 
+    // Graphics for views drawn at the user interface scale instead of the level zoom
+    pub fn unzoomed(&self) -> Graphics {
+        Graphics {
+            zoom: self.render_multiplier * 100,
+            ..*self
+        }
+    }
+
+    pub fn zoom_in(&mut self) {
+        if let Some(zoom) = ZOOM_LEVELS.iter().find(|zoom| **zoom > self.zoom) {
+            self.zoom = *zoom;
+        }
+    }
+
+    pub fn zoom_out(&mut self) {
+        if let Some(zoom) = ZOOM_LEVELS.iter().rev().find(|zoom| **zoom < self.zoom) {
+            self.zoom = *zoom;
+        }
+    }
+
     pub fn get_render_size(&self) -> u32 {
-        TILE_SIZE * self.render_multiplier
+        TILE_SIZE * self.zoom / 100
     }
 
     pub fn get_x_tiles_per_screen(&self) -> u32 {
         (self.resolution_x + self.get_render_size() - 1) / self.get_render_size()
     }
 
-    pub fn get_full_x_tiles_per_screen(&self) -> u32 {
-        self.resolution_x / self.get_render_size()
-    }
-
     pub fn get_y_tiles_per_screen(&self) -> u32 {
         (self.resolution_y + self.get_render_size() - 1) / self.get_render_size()
     }
-
-    pub fn get_full_y_tiles_per_screen(&self) -> u32 {
-        self.resolution_y / self.get_render_size()
-    }
 }
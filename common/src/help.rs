@@ -5,27 +5,36 @@ use crate::render::Renderer;
 use crate::types::Mode;
 use crate::EventResult;
 
-const LINES: [&str; 16] = [
+const LINES: [&str; 27] = [
     "ESC - quit",
     "F1   - this help",
-    "F2   - save level",
+    "F2   - save level, CTRL+S - save with current name",
     "F3   - load level",
     "F4   - create new level",
+    "F5   - clear manual shadows",
     "F6   - enable/disable automatic shadows",
     "F7   - edit general level variables",
     "F8/F9 - edit random crates for normal/dm games",
     " ",
     "- EDITOR -",
     "Q/W  - place/delete spotlights",
-    "A/S  - place/delete steams",
+    "A/S  - place and aim/delete steams",
     "Z/X/C - place/delete crates",
-    "1/2  - place pl1/pl2 start",
+    "CLICK - select object to edit its properties",
+    "TAB  - edit selected objects with keys, ESC: back",
+    "B    - box select objects",
+    "DEL  - delete selected objects",
+    "DRAG - move objects and pl1/pl2 starts, SHIFT: one axis",
+    "R    - replace pointed tiles with selected tile",
     "SPACE - tile selection/editing mode",
     "ARROW KEYS - move viewport",
+    "WHEEL/SHIFT+WHEEL - scroll, adjust selected objects",
+    "MIDDLE DRAG - move viewport",
+    "+/- or CTRL+WHEEL - zoom around the cursor",
+    "M    - show/hide minimap, click or drag it to move viewport",
+    "L    - grid, layer visibility and lighting preview",
 ];
 
-const WINDOW_LINES: [&str; 3] = [" ", "- WINDOW -", "+/- adjust rendering size"];
-
 pub struct HelpState;
 
 impl HelpState {
@@ -53,11 +62,5 @@ impl HelpState {
             font.render_text(renderer, line_text, (10, position));
             position += font.line_height() + 2;
         }
-        if context.graphics.supports_scaling {
-            for line_text in &WINDOW_LINES {
-                font.render_text(renderer, line_text, (10, position));
-                position += font.line_height() + 2;
-            }
-        }
     }
 }
@@ -0,0 +1,464 @@
+use crate::context::Context;
+use crate::event::Keycode;
+use crate::font::Font;
+use crate::level::{crates, CrateClass, Level, LevelObject, StaticCrate, Steam};
+use crate::load_level::LevelLister;
+use crate::render::{Rect, Renderer, RendererColor, Texture};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Property {
+    SpotlightIntensity,
+    SteamAngle,
+    SteamRange,
+    CrateVariant,
+    CrateClass,
+    CrateType,
+}
+
+const ALL_PROPERTIES: &[Property] = &[
+    Property::SpotlightIntensity,
+    Property::SteamAngle,
+    Property::SteamRange,
+    Property::CrateVariant,
+    Property::CrateClass,
+    Property::CrateType,
+];
+
+const PANEL_WIDTH: u32 = 250;
+const MAX_INPUT_LENGTH: usize = 3;
+
+// Properties of the selected objects, editable with arrow keys, typed numbers or mouse clicks
+// on the < and > buttons. With multiple objects selected, a change applies to every object
+// having the property. The keys go to the inspector only while it has focus, which a click on
+// the panel or Tab gives, so that the arrow keys keep scrolling the level after placing objects.
+#[derive(Default)]
+pub struct Inspector {
+    selected_property: usize,
+    input: String,
+    focused: bool,
+}
+
+struct PanelLayout {
+    x: u32,
+    y: u32,
+    width: u32,
+    row_height: u32,
+    rows: u32,
+}
+
+impl PanelLayout {
+    fn new<T>(font: &Font<T>, resolution_x: u32, properties: usize) -> Self {
+        let width = font.px(PANEL_WIDTH);
+        PanelLayout {
+            x: resolution_x - width - font.px(4),
+            y: font.px(20),
+            width,
+            row_height: font.line_height() + font.px(4),
+            rows: properties as u32 + 2, // title and instructions
+        }
+    }
+
+    fn rect(&self) -> Rect {
+        Rect::new(
+            self.x as i32,
+            self.y as i32,
+            self.width,
+            self.rows * self.row_height,
+        )
+    }
+
+    fn row_y(&self, row: u32) -> u32 {
+        self.y + row * self.row_height + 2
+    }
+
+    fn contains(&self, point: &(u32, u32)) -> bool {
+        point.0 >= self.x
+            && point.0 < self.x + self.width
+            && point.1 >= self.y
+            && point.1 < self.y + self.rows * self.row_height
+    }
+}
+
+impl Inspector {
+    pub fn reset(&mut self) {
+        self.selected_property = 0;
+        self.input.clear();
+        self.focused = false;
+    }
+
+    // Returns false if the key is not used by the inspector
+    pub fn handle_key<L: LevelLister, T: Texture>(
+        &mut self,
+        context: &mut Context<L, T>,
+        objects: &[LevelObject],
+        keycode: &Keycode,
+    ) -> bool {
+        let level = &mut context.level;
+        let properties = get_properties(objects);
+        if properties.is_empty() {
+            return false;
+        }
+        if *keycode == Keycode::Tab || (self.focused && *keycode == Keycode::Escape) {
+            self.focused = *keycode == Keycode::Tab && !self.focused;
+            self.input.clear();
+            return true;
+        }
+        if !self.focused {
+            return false;
+        }
+        self.selected_property = std::cmp::min(self.selected_property, properties.len() - 1);
+        let property = properties[self.selected_property];
+        match keycode {
+            Keycode::Up => {
+                if self.selected_property > 0 {
+                    self.selected_property -= 1;
+                    self.input.clear();
+                }
+            }
+            Keycode::Down => {
+                if self.selected_property < properties.len() - 1 {
+                    self.selected_property += 1;
+                    self.input.clear();
+                }
+            }
+            Keycode::Left => {
+                self.input.clear();
+                adjust_values(level, objects, property, -1);
+                context.unsaved_changes = true;
+            }
+            Keycode::Right => {
+                self.input.clear();
+                adjust_values(level, objects, property, 1);
+                context.unsaved_changes = true;
+            }
+            Keycode::Return | Keycode::KpEnter => {
+                if let Ok(value) = self.input.parse() {
+                    for position in get_positions(level, objects, property) {
+                        set_value(level, &position, property, value);
+                    }
+                    context.unsaved_changes = true;
+                }
+                self.input.clear();
+            }
+            Keycode::Backspace => {
+                self.input.pop();
+            }
+            keycode => match get_digit(keycode) {
+                Some(digit) if self.input.len() < MAX_INPUT_LENGTH => self.input.push(digit),
+                Some(_) => {}
+                None => return false,
+            },
+        }
+        true
+    }
+
+    // Returns false if the click is outside of the panel
+    pub fn handle_click<L: LevelLister, T: Texture>(
+        &mut self,
+        context: &mut Context<L, T>,
+        objects: &[LevelObject],
+    ) -> bool {
+        let properties = get_properties(objects);
+        if properties.is_empty() {
+            return false;
+        }
+        let layout = PanelLayout::new(
+            &context.font,
+            context.graphics.resolution_x,
+            properties.len(),
+        );
+        if !layout.contains(&context.mouse) {
+            self.focused = false;
+            self.input.clear();
+            return false;
+        }
+        self.focused = true;
+        let row = (context.mouse.1 - layout.y) / layout.row_height;
+        if row == 0 || row > properties.len() as u32 {
+            return true;
+        }
+        self.selected_property = row as usize - 1;
+        self.input.clear();
+        let property = properties[self.selected_property];
+        let x = context.mouse.0 - layout.x;
+        if x >= context.font.px(76) && x < context.font.px(90) {
+            adjust_values(&mut context.level, objects, property, -1);
+            context.unsaved_changes = true;
+        } else if x >= layout.width - context.font.px(16) {
+            adjust_values(&mut context.level, objects, property, 1);
+            context.unsaved_changes = true;
+        }
+        true
+    }
+
+    pub fn render<L: LevelLister, R: Renderer>(
+        &self,
+        renderer: &mut R,
+        context: &Context<L, R::Texture>,
+        objects: &[LevelObject],
+    ) {
+        let properties = get_properties(objects);
+        if properties.is_empty() {
+            return;
+        }
+        let selected_property = std::cmp::min(self.selected_property, properties.len() - 1);
+        let font = &context.font;
+        let layout = PanelLayout::new(font, context.graphics.resolution_x, properties.len());
+        renderer.fill_rect(&layout.rect(), RendererColor::Black);
+        renderer.draw_rect(
+            &layout.rect(),
+            if self.focused {
+                RendererColor::Yellow
+            } else {
+                RendererColor::White
+            },
+        );
+        let title = match objects {
+            [object] => get_object_name(object).to_string(),
+            _ => format!("{} OBJECTS", objects.len()),
+        };
+        font.render_text(renderer, &title, (layout.x + font.px(6), layout.row_y(0)));
+        for (index, property) in properties.iter().enumerate() {
+            let y = layout.row_y(index as u32 + 1);
+            if index == selected_property {
+                font.render_text(renderer, "*", (layout.x + font.px(4), y));
+            }
+            font.render_text(
+                renderer,
+                get_property_name(*property),
+                (layout.x + font.px(12), y),
+            );
+            font.render_text(renderer, "<", (layout.x + font.px(80), y));
+            let value = if index == selected_property && !self.input.is_empty() {
+                format!("{}_", self.input)
+            } else {
+                get_values_text(&context.level, objects, *property)
+            };
+            font.render_text(renderer, &value, (layout.x + font.px(92), y));
+            font.render_text(renderer, ">", (layout.x + layout.width - font.px(12), y));
+        }
+        font.render_text(
+            renderer,
+            if self.focused {
+                "UP/DOWN, LEFT/RIGHT, 0-9 + ENTER"
+            } else {
+                "TAB OR CLICK TO USE KEYS"
+            },
+            (
+                layout.x + font.px(6),
+                layout.row_y(properties.len() as u32 + 1),
+            ),
+        );
+    }
+}
+
+// Mouse wheel adjusts spotlight intensity, steam range and crate type
+pub fn adjust_with_wheel(level: &mut Level, objects: &[LevelObject], direction: i32) {
+    for property in [
+        Property::SpotlightIntensity,
+        Property::SteamRange,
+        Property::CrateType,
+    ] {
+        adjust_values(level, objects, property, direction.signum());
+    }
+}
+
+// Object name followed by its property values, e.g. "STEAM angle 90 range 3"
+pub fn get_object_description(level: &Level, object: &LevelObject) -> String {
+    let mut description = match object {
+        LevelObject::PlayerStart(player) => format!("PL{}", player),
+        _ => get_object_name(object).to_string(),
+    };
+    let position = level.get_object_position(object);
+    for property in get_properties(std::slice::from_ref(object)) {
+        description += &format!(
+            " {} {}",
+            get_property_name(property),
+            get_value_text(level, &position, property)
+        );
+    }
+    description
+}
+
+fn get_properties(objects: &[LevelObject]) -> Vec<Property> {
+    ALL_PROPERTIES
+        .iter()
+        .copied()
+        .filter(|property| objects.iter().any(|object| has_property(object, *property)))
+        .collect()
+}
+
+fn has_property(object: &LevelObject, property: Property) -> bool {
+    match property {
+        Property::SpotlightIntensity => matches!(object, LevelObject::Spotlight(_)),
+        Property::SteamAngle | Property::SteamRange => matches!(object, LevelObject::Steam(_)),
+        Property::CrateVariant | Property::CrateClass | Property::CrateType => {
+            matches!(object, LevelObject::Crate(_))
+        }
+    }
+}
+
+// Positions of the objects having the property
+fn get_positions(level: &Level, objects: &[LevelObject], property: Property) -> Vec<(u32, u32)> {
+    objects
+        .iter()
+        .filter(|object| has_property(object, property))
+        .map(|object| level.get_object_position(object))
+        .collect()
+}
+
+fn get_object_name(object: &LevelObject) -> &'static str {
+    match object {
+        LevelObject::Spotlight(_) => "SPOTLIGHT",
+        LevelObject::Steam(_) => "STEAM",
+        LevelObject::Crate(_) => "CRATE",
+        LevelObject::PlayerStart(_) => "PLAYER START",
+    }
+}
+
+fn get_property_name(property: Property) -> &'static str {
+    match property {
+        Property::SpotlightIntensity => "intensity",
+        Property::SteamAngle => "angle",
+        Property::SteamRange => "range",
+        Property::CrateVariant => "game",
+        Property::CrateClass => "class",
+        Property::CrateType => "item",
+    }
+}
+
+fn get_value(level: &Level, position: &(u32, u32), property: Property) -> u32 {
+    match property {
+        Property::SpotlightIntensity => level.get_spotlight_from_level(position) as u32,
+        Property::SteamAngle => level.get_steam_from_level(position).angle as u32,
+        Property::SteamRange => level.get_steam_from_level(position).range as u32,
+        Property::CrateVariant => level.get_crate_from_level(position).crate_variant as u32,
+        Property::CrateClass => level.get_crate_from_level(position).crate_class as u32,
+        Property::CrateType => level.get_crate_from_level(position).crate_type as u32,
+    }
+}
+
+fn get_max_value(level: &Level, position: &(u32, u32), property: Property) -> u32 {
+    match property {
+        Property::SpotlightIntensity => 9,
+        Property::SteamAngle => 355,
+        Property::SteamRange => 6,
+        Property::CrateVariant => StaticCrate::Deathmatch as u32,
+        Property::CrateClass => CrateClass::Energy as u32,
+        Property::CrateType => {
+            crates(level.get_crate_from_level(position).crate_class).len() as u32 - 1
+        }
+    }
+}
+
+fn get_value_text(level: &Level, position: &(u32, u32), property: Property) -> String {
+    match property {
+        Property::CrateVariant => match level.get_crate_from_level(position).crate_variant {
+            StaticCrate::Normal => "normal".to_string(),
+            StaticCrate::Deathmatch => "deathmatch".to_string(),
+        },
+        Property::CrateClass => match level.get_crate_from_level(position).crate_class {
+            CrateClass::Weapon => "weapon".to_string(),
+            CrateClass::Bullet => "bullet".to_string(),
+            CrateClass::Energy => "energy".to_string(),
+        },
+        Property::CrateType => {
+            let crate_item = level.get_crate_from_level(position);
+            crates(crate_item.crate_class)[crate_item.crate_type as usize].to_string()
+        }
+        _ => get_value(level, position, property).to_string(),
+    }
+}
+
+fn get_values_text(level: &Level, objects: &[LevelObject], property: Property) -> String {
+    let mut texts = get_positions(level, objects, property)
+        .into_iter()
+        .map(|position| get_value_text(level, &position, property));
+    let text = texts.next().unwrap();
+    if texts.all(|other| other == text) {
+        text
+    } else {
+        "mixed".to_string()
+    }
+}
+
+fn adjust_values(level: &mut Level, objects: &[LevelObject], property: Property, direction: i32) {
+    for position in get_positions(level, objects, property) {
+        adjust_value(level, &position, property, direction);
+    }
+}
+
+// Angle wraps around, other values stop at their limits
+fn adjust_value(level: &mut Level, position: &(u32, u32), property: Property, direction: i32) {
+    let step = if property == Property::SteamAngle {
+        5
+    } else {
+        1
+    };
+    let value = get_value(level, position, property) as i32 + direction * step;
+    let value = if property == Property::SteamAngle {
+        (value + 360) % 360
+    } else {
+        value.clamp(0, get_max_value(level, position, property) as i32)
+    };
+    set_value(level, position, property, value as u32);
+}
+
+fn set_value(level: &mut Level, position: &(u32, u32), property: Property, value: u32) {
+    let value = std::cmp::min(value, get_max_value(level, position, property));
+    match property {
+        Property::SpotlightIntensity => level.put_spotlight_to_level(position, value as u8),
+        Property::SteamAngle | Property::SteamRange => {
+            let steam = level.get_steam_from_level(position);
+            let steam = if property == Property::SteamAngle {
+                Steam {
+                    angle: (value - value % 5) as u16,
+                    ..steam
+                }
+            } else {
+                Steam {
+                    range: value as u8,
+                    ..steam
+                }
+            };
+            level.put_steam_to_level(position, &steam);
+        }
+        Property::CrateVariant | Property::CrateClass | Property::CrateType => {
+            let mut crate_item = *level.get_crate_from_level(position);
+            match property {
+                Property::CrateVariant => {
+                    crate_item.crate_variant = if value == 0 {
+                        StaticCrate::Normal
+                    } else {
+                        StaticCrate::Deathmatch
+                    };
+                }
+                Property::CrateClass => {
+                    if crate_item.crate_class as u32 != value {
+                        crate_item.crate_class = CrateClass::from_u32(value);
+                        crate_item.crate_type = 0;
+                    }
+                }
+                _ => crate_item.crate_type = value as u8,
+            }
+            level.put_crate_to_level(position, &crate_item);
+        }
+    }
+}
+
+fn get_digit(keycode: &Keycode) -> Option<char> {
+    let digit = match keycode {
+        Keycode::Num0 => '0',
+        Keycode::Num1 => '1',
+        Keycode::Num2 => '2',
+        Keycode::Num3 => '3',
+        Keycode::Num4 => '4',
+        Keycode::Num5 => '5',
+        Keycode::Num6 => '6',
+        Keycode::Num7 => '7',
+        Keycode::Num8 => '8',
+        Keycode::Num9 => '9',
+        _ => return None,
+    };
+    Some(digit)
+}
@@ -0,0 +1,224 @@
+use crate::context::Context;
+use crate::event::Keycode;
+use crate::font::Font;
+use crate::level::StaticCrate;
+use crate::load_level::LevelLister;
+use crate::render::{Rect, Renderer, RendererColor, Texture};
+use crate::types::TextureType;
+
+const MENU_WIDTH: u32 = 220;
+const MENU_ITEMS: u32 = 10;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CrateFilter {
+    All,
+    Normal,
+    Deathmatch,
+    None,
+}
+
+// What the editor draws of the level. The menu toggled with L lists the layers, number keys
+// or mouse clicks toggle them.
+pub struct Layers {
+    pub grid: bool,
+    pub floor: bool,
+    pub walls: bool,
+    pub shadows: bool,
+    pub spotlights: bool,
+    pub steams: bool,
+    pub crates: CrateFilter,
+    pub player_labels: bool,
+    pub lighting: bool, // darkened level lit by the spotlights, as in the game
+    pub steam_animation: bool,
+    menu_open: bool,
+}
+
+impl Default for Layers {
+    fn default() -> Self {
+        Layers {
+            grid: false,
+            floor: true,
+            walls: true,
+            shadows: true,
+            spotlights: true,
+            steams: true,
+            crates: CrateFilter::All,
+            player_labels: true,
+            lighting: false,
+            steam_animation: false,
+            menu_open: false,
+        }
+    }
+}
+
+struct MenuLayout {
+    x: u32,
+    y: u32,
+    width: u32,
+    row_height: u32,
+}
+
+impl MenuLayout {
+    fn new<T>(font: &Font<T>) -> Self {
+        MenuLayout {
+            x: font.px(4),
+            y: font.px(20),
+            width: font.px(MENU_WIDTH),
+            row_height: font.line_height() + font.px(4),
+        }
+    }
+
+    fn rect(&self) -> Rect {
+        Rect::new(
+            self.x as i32,
+            self.y as i32,
+            self.width,
+            (MENU_ITEMS + 1) * self.row_height,
+        )
+    }
+
+    fn row_y(&self, row: u32) -> u32 {
+        self.y + row * self.row_height + 2
+    }
+
+    fn contains(&self, point: &(u32, u32)) -> bool {
+        point.0 >= self.x
+            && point.0 < self.x + self.width
+            && point.1 >= self.y
+            && point.1 < self.y + (MENU_ITEMS + 1) * self.row_height
+    }
+}
+
+impl Layers {
+    pub fn toggle_menu(&mut self) {
+        self.menu_open = !self.menu_open;
+    }
+
+    pub fn shows_texture(&self, texture_type: TextureType) -> bool {
+        match texture_type {
+            TextureType::Floor => self.floor,
+            TextureType::Walls => self.walls,
+            TextureType::Shadow => self.shadows,
+        }
+    }
+
+    pub fn shows_crate(&self, crate_variant: StaticCrate) -> bool {
+        match self.crates {
+            CrateFilter::All => true,
+            CrateFilter::Normal => crate_variant == StaticCrate::Normal,
+            CrateFilter::Deathmatch => crate_variant == StaticCrate::Deathmatch,
+            CrateFilter::None => false,
+        }
+    }
+
+    // Returns false if the menu is closed or the key is not used by it
+    pub fn handle_key(&mut self, keycode: &Keycode) -> bool {
+        if !self.menu_open {
+            return false;
+        }
+        match keycode {
+            Keycode::Escape | Keycode::L => self.menu_open = false,
+            Keycode::Num1 => self.toggle(0),
+            Keycode::Num2 => self.toggle(1),
+            Keycode::Num3 => self.toggle(2),
+            Keycode::Num4 => self.toggle(3),
+            Keycode::Num5 => self.toggle(4),
+            Keycode::Num6 => self.toggle(5),
+            Keycode::Num7 => self.toggle(6),
+            Keycode::Num8 => self.toggle(7),
+            Keycode::Num9 => self.toggle(8),
+            Keycode::Num0 => self.toggle(9),
+            _ => return false,
+        }
+        true
+    }
+
+    // Returns false if the click is outside of the menu
+    pub fn handle_click<L: LevelLister, T: Texture>(&mut self, context: &Context<L, T>) -> bool {
+        if !self.menu_open {
+            return false;
+        }
+        let layout = MenuLayout::new(&context.font);
+        if !layout.contains(&context.mouse) {
+            return false;
+        }
+        let row = (context.mouse.1 - layout.y) / layout.row_height;
+        if row > 0 {
+            self.toggle(row - 1);
+        }
+        true
+    }
+
+    pub fn render_menu<L: LevelLister, R: Renderer>(
+        &self,
+        renderer: &mut R,
+        context: &Context<L, R::Texture>,
+    ) {
+        if !self.menu_open {
+            return;
+        }
+        let font = &context.font;
+        let layout = MenuLayout::new(font);
+        renderer.fill_rect(&layout.rect(), RendererColor::Black);
+        renderer.draw_rect(&layout.rect(), RendererColor::White);
+        font.render_text(
+            renderer,
+            "LAYERS (L to close)",
+            (layout.x + font.px(6), layout.row_y(0)),
+        );
+        for item in 0..MENU_ITEMS {
+            font.render_text(
+                renderer,
+                &format!("{} {}", (item + 1) % 10, self.get_item_text(item)),
+                (layout.x + font.px(6), layout.row_y(item + 1)),
+            );
+        }
+    }
+
+    fn toggle(&mut self, item: u32) {
+        match item {
+            0 => self.grid = !self.grid,
+            1 => self.floor = !self.floor,
+            2 => self.walls = !self.walls,
+            3 => self.shadows = !self.shadows,
+            4 => self.spotlights = !self.spotlights,
+            5 => self.steams = !self.steams,
+            6 => {
+                self.crates = match self.crates {
+                    CrateFilter::All => CrateFilter::Normal,
+                    CrateFilter::Normal => CrateFilter::Deathmatch,
+                    CrateFilter::Deathmatch => CrateFilter::None,
+                    CrateFilter::None => CrateFilter::All,
+                }
+            }
+            7 => self.player_labels = !self.player_labels,
+            8 => self.lighting = !self.lighting,
+            9 => self.steam_animation = !self.steam_animation,
+            _ => {}
+        }
+    }
+
+    fn get_item_text(&self, item: u32) -> String {
+        let (name, shown) = match item {
+            0 => ("grid", self.grid),
+            1 => ("floor", self.floor),
+            2 => ("walls", self.walls),
+            3 => ("shadows", self.shadows),
+            4 => ("spotlights", self.spotlights),
+            5 => ("steams", self.steams),
+            6 => {
+                return match self.crates {
+                    CrateFilter::All => "crates: all",
+                    CrateFilter::Normal => "crates: normal only",
+                    CrateFilter::Deathmatch => "crates: deathmatch only",
+                    CrateFilter::None => "crates: hidden",
+                }
+                .to_string()
+            }
+            7 => ("player labels", self.player_labels),
+            8 => ("lighting preview", self.lighting),
+            _ => ("steam animation", self.steam_animation),
+        };
+        format!("{}: {}", name, if shown { "shown" } else { "hidden" })
+    }
+}
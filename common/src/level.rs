@@ -1,6 +1,7 @@
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use crate::shadow_rules::ShadowRules;
 use crate::types::*;
 use crate::util::*;
 
@@ -67,11 +68,37 @@ pub fn crates(cls: CrateClass) -> &'static [&'static str] {
     }
 }
 
+// Index of the crate in ALL_CRATES
+pub fn get_crate_index(cls: CrateClass, crate_type: u8) -> usize {
+    let offset = match cls {
+        CrateClass::Weapon => 0,
+        CrateClass::Bullet => weapon_crates().len(),
+        CrateClass::Energy => weapon_crates().len() + bullet_crates().len(),
+    };
+    offset + crate_type as usize
+}
+
+pub const ENEMY_NAMES: [&str; DIFF_ENEMIES] = [
+    "pistol boys",
+    "shotgun maniacs",
+    "uzi rebels",
+    "commandos",
+    "granade mofos",
+    "civilians",
+    "punishers",
+    "flamers",
+];
+
 const DIFF_WEAPONS: usize = 11;
 const DIFF_BULLETS: usize = 9;
 const DIFF_ENEMIES: usize = 8;
 
-const VERSION: u32 = 5;
+pub(crate) const VERSION: u32 = 5;
+// Static crates were added in this version
+pub(crate) const STATIC_CRATES_VERSION: u32 = 5;
+const SHADOW_LOCK_VERSION: u32 = 1;
+
+pub const SHADOW_LOCK_EXTENSION: &str = "SHL";
 
 type Position = (u32, u32);
 
@@ -116,21 +143,70 @@ pub struct Crates {
     pub staticc: HashMap<Position, StaticCrateType>,
 }
 
+// Inclusive top left and bottom right tile positions
+pub type TileArea = (Position, Position);
+
+#[derive(Clone, Copy)]
+pub struct TileMatch {
+    pub texture_type: TextureType,
+    pub id: u32,
+    pub shadow: Option<u32>, // None matches any shadow
+}
+
+impl TileMatch {
+    fn matches(&self, tile: &Tile) -> bool {
+        tile.texture_type == self.texture_type
+            && tile.id == self.id
+            && self.shadow.is_none_or(|shadow| tile.shadow == shadow)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LevelObject {
+    Spotlight(Position),
+    Steam(Position),
+    Crate(Position),
+    PlayerStart(u8), // 1 or 2
+}
+
+impl LevelObject {
+    // Order of overlapping objects at the same distance from the mouse
+    fn get_pick_order(&self) -> u8 {
+        match self {
+            LevelObject::Steam(_) => 0,
+            LevelObject::Crate(_) => 1,
+            LevelObject::PlayerStart(player) => 1 + player,
+            LevelObject::Spotlight(_) => 4,
+        }
+    }
+
+    fn moved_to(&self, level_coordinates: Position) -> LevelObject {
+        match self {
+            LevelObject::Spotlight(_) => LevelObject::Spotlight(level_coordinates),
+            LevelObject::Steam(_) => LevelObject::Steam(level_coordinates),
+            LevelObject::Crate(_) => LevelObject::Crate(level_coordinates),
+            LevelObject::PlayerStart(_) => *self,
+        }
+    }
+}
+
 pub struct Level {
     pub tiles: Tiles,
     pub p1_position: Position,
     pub p2_position: Position,
-    pub scroll: Position,
     pub spotlights: HashMap<Position, u8>, // 0-9 intensity
     pub steams: HashMap<Position, Steam>,
     pub general_info: GeneralInfo,
     pub crates: Crates,
+    pub shadow_locks: HashSet<Position>, // manually set shadows skipped by create_shadows
 }
 
 #[derive(Debug)]
 pub enum FileTypeError {
     InvalidVersion,
     InvalidLevelSize,
+    InvalidTextureType(u32),
+    InvalidCrateClass(u32),
 }
 
 #[derive(Debug)]
@@ -157,7 +233,6 @@ impl Level {
             tiles: Level::init_default_level(size),
             p1_position: (1, 1),
             p2_position: (1, 3),
-            scroll: (0, 0),
             spotlights: HashMap::new(),
             steams: HashMap::new(),
             general_info: GeneralInfo {
@@ -180,8 +255,9 @@ impl Level {
                 },
                 staticc: HashMap::new(),
             },
+            shadow_locks: HashSet::new(),
         };
-        level.create_shadows();
+        level.create_shadows(&ShadowRules::default());
         level
     }
 
@@ -295,10 +371,53 @@ impl Level {
                     Some(id) => id + 1,
                     None => 0,
                 };
+                self.shadow_locks.insert((x as u32, y as u32));
             }
         }
     }
 
+    pub fn get_tile_match(&self, position: &Position) -> Option<TileMatch> {
+        self.tiles
+            .get(position.1 as usize)
+            .and_then(|row| row.get(position.0 as usize))
+            .map(|tile| TileMatch {
+                texture_type: tile.texture_type,
+                id: tile.id,
+                shadow: Some(tile.shadow),
+            })
+    }
+
+    pub fn find_tiles(&self, tile_match: &TileMatch, area: Option<TileArea>) -> Vec<Position> {
+        let ((x0, y0), (x1, y1)) = area.unwrap_or((
+            (0, 0),
+            (self.tiles[0].len() as u32 - 1, self.tiles.len() as u32 - 1),
+        ));
+        let mut positions = Vec::new();
+        for y in y0..=std::cmp::min(y1, self.tiles.len() as u32 - 1) {
+            for x in x0..=std::cmp::min(x1, self.tiles[0].len() as u32 - 1) {
+                if tile_match.matches(&self.tiles[y as usize][x as usize]) {
+                    positions.push((x, y));
+                }
+            }
+        }
+        positions
+    }
+
+    pub fn replace_tiles(
+        &mut self,
+        tile_match: &TileMatch,
+        replacement: (TextureType, u32),
+        area: Option<TileArea>,
+    ) -> usize {
+        let positions = self.find_tiles(tile_match, area);
+        for (x, y) in &positions {
+            let tile = &mut self.tiles[*y as usize][*x as usize];
+            tile.texture_type = replacement.0;
+            tile.id = replacement.1;
+        }
+        positions.len()
+    }
+
     pub fn put_spotlight_to_level(&mut self, level_coordinates: &Position, spotlight: u8) {
         if spotlight < 10 {
             self.spotlights.insert(*level_coordinates, spotlight);
@@ -309,11 +428,7 @@ impl Level {
         *self.spotlights.get(level_coordinates).unwrap()
     }
 
-    pub fn delete_spotlight_if_near(
-        &mut self,
-        level_coordinates: &Position,
-        render_multiplier: u32,
-    ) {
+    pub fn delete_spotlight_if_near(&mut self, level_coordinates: &Position, zoom: u32) {
         let mut to_be_removed = Vec::new();
         {
             let distances: Vec<_> = self
@@ -327,7 +442,7 @@ impl Level {
                 .collect();
             for spotlight in distances {
                 if get_spotlight_render_radius(&spotlight.1) as f64
-                    >= spotlight.2 * render_multiplier as f64
+                    >= spotlight.2 * zoom as f64 / 100.0
                 {
                     to_be_removed.push(*spotlight.0);
                 }
@@ -348,7 +463,7 @@ impl Level {
         *self.steams.get(level_coordinates).unwrap()
     }
 
-    pub fn delete_steam_if_near(&mut self, level_coordinates: &Position, render_multiplier: u32) {
+    pub fn delete_steam_if_near(&mut self, level_coordinates: &Position, zoom: u32) {
         let mut to_be_removed = Vec::new();
         {
             let distances: Vec<_> = self
@@ -361,7 +476,7 @@ impl Level {
                 })
                 .collect();
             for steam in distances {
-                if get_steam_render_radius() as f64 >= steam.1 * render_multiplier as f64 {
+                if get_steam_render_radius() as f64 >= steam.1 * zoom as f64 / 100.0 {
                     to_be_removed.push(*steam.0);
                 }
             }
@@ -383,13 +498,13 @@ impl Level {
         self.crates.staticc.get(level_coordinates).unwrap()
     }
 
-    pub fn delete_crate_if_near(&mut self, level_coordinates: &Position, render_multiplier: u32) {
+    pub fn delete_crate_if_near(&mut self, level_coordinates: &Position, zoom: u32) {
         let mut to_be_removed = Vec::new();
         for crate_coordinates in self.crates.staticc.keys() {
             if check_box_click(
                 level_coordinates,
                 crate_coordinates,
-                get_crate_render_size() / render_multiplier,
+                get_crate_render_size() * 100 / zoom,
             ) {
                 to_be_removed.push(*crate_coordinates);
             }
@@ -399,43 +514,300 @@ impl Level {
         }
     }
 
-    pub fn create_shadows(&mut self) {
-        for y in (0..self.tiles.len()).rev() {
-            for x in 0..self.tiles[y].len() {
-                if self.tiles[y][x].texture_type != TextureType::Walls {
-                    let on_right = if x < self.tiles[y].len() - 1 {
-                        self.tiles[y][x + 1].texture_type
-                    } else {
-                        TextureType::Floor
-                    };
-                    let on_top_right = if y > 0 && x < self.tiles[y].len() - 1 {
-                        self.tiles[y - 1][x + 1].texture_type
-                    } else {
-                        TextureType::Floor
-                    };
-                    let on_top = if y > 0 {
-                        self.tiles[y - 1][x].texture_type
-                    } else {
-                        TextureType::Floor
-                    };
-                    self.tiles[y][x].shadow = if on_top_right == TextureType::Walls
-                        || (on_right == TextureType::Walls && on_top == TextureType::Walls)
-                    {
-                        1
-                    } else if on_top == TextureType::Walls {
-                        3
-                    } else if on_right == TextureType::Walls {
-                        2
-                    } else {
-                        0
-                    };
+    // Nearest object under the level coordinates. Objects at the same distance are picked by
+    // kind and then by position, so the pick does not depend on the HashMap order.
+    pub fn find_object(&self, level_coordinates: &Position, zoom: u32) -> Option<LevelObject> {
+        let near = |coordinates: &Position, radius: u32| {
+            radius as f64
+                >= get_distance_between_points(level_coordinates, coordinates) * zoom as f64 / 100.0
+        };
+        let crate_size = get_crate_render_size() * 100 / zoom;
+        let tile = (
+            level_coordinates.0 / TILE_SIZE,
+            level_coordinates.1 / TILE_SIZE,
+        );
+        // Center which the distance is measured from and the object
+        let mut hits: Vec<(Position, LevelObject)> = Vec::new();
+        hits.extend(
+            self.steams
+                .keys()
+                .filter(|coordinates| near(coordinates, get_steam_render_radius()))
+                .map(|coordinates| (*coordinates, LevelObject::Steam(*coordinates))),
+        );
+        hits.extend(
+            self.crates
+                .staticc
+                .keys()
+                .filter(|coordinates| check_box_click(level_coordinates, coordinates, crate_size))
+                .map(|coordinates| {
+                    (
+                        (
+                            coordinates.0 + crate_size / 2,
+                            coordinates.1 + crate_size / 2,
+                        ),
+                        LevelObject::Crate(*coordinates),
+                    )
+                }),
+        );
+        for (player, position) in [(1, self.p1_position), (2, self.p2_position)] {
+            if tile == position {
+                hits.push((
+                    (
+                        position.0 * TILE_SIZE + TILE_SIZE / 2,
+                        position.1 * TILE_SIZE + TILE_SIZE / 2,
+                    ),
+                    LevelObject::PlayerStart(player),
+                ));
+            }
+        }
+        hits.extend(
+            self.spotlights
+                .iter()
+                .filter(|(coordinates, spotlight)| {
+                    near(coordinates, get_spotlight_render_radius(spotlight))
+                })
+                .map(|(coordinates, _)| (*coordinates, LevelObject::Spotlight(*coordinates))),
+        );
+        let distance = |center: &Position| get_distance_between_points(level_coordinates, center);
+        let position = |object: &LevelObject| {
+            let position = self.get_object_position(object);
+            (position.1, position.0)
+        };
+        hits.into_iter()
+            .min_by(|(center_a, a), (center_b, b)| {
+                distance(center_a)
+                    .total_cmp(&distance(center_b))
+                    .then(a.get_pick_order().cmp(&b.get_pick_order()))
+                    .then(position(a).cmp(&position(b)))
+            })
+            .map(|(_, object)| object)
+    }
+
+    pub fn get_object_position(&self, object: &LevelObject) -> Position {
+        match object {
+            LevelObject::Spotlight(coordinates)
+            | LevelObject::Steam(coordinates)
+            | LevelObject::Crate(coordinates) => *coordinates,
+            LevelObject::PlayerStart(player) => {
+                let position = if *player == 1 {
+                    self.p1_position
                 } else {
-                    self.tiles[y][x].shadow = 0;
+                    self.p2_position
+                };
+                (position.0 * TILE_SIZE, position.1 * TILE_SIZE)
+            }
+        }
+    }
+
+    pub fn has_object(&self, object: &LevelObject) -> bool {
+        match object {
+            LevelObject::Spotlight(coordinates) => self.spotlights.contains_key(coordinates),
+            LevelObject::Steam(coordinates) => self.steams.contains_key(coordinates),
+            LevelObject::Crate(coordinates) => self.crates.staticc.contains_key(coordinates),
+            LevelObject::PlayerStart(_) => true,
+        }
+    }
+
+    // Returns the moved object, or the original one if the new position is taken or outside
+    // of the level
+    pub fn move_object(
+        &mut self,
+        object: &LevelObject,
+        level_coordinates: &Position,
+    ) -> LevelObject {
+        if level_coordinates.0 >= self.tiles[0].len() as u32 * TILE_SIZE
+            || level_coordinates.1 >= self.tiles.len() as u32 * TILE_SIZE
+        {
+            return *object;
+        }
+        match object {
+            LevelObject::Spotlight(coordinates) => {
+                if self.spotlights.contains_key(level_coordinates) {
+                    return *object;
+                }
+                let spotlight = self.spotlights.remove(coordinates).unwrap();
+                self.spotlights.insert(*level_coordinates, spotlight);
+                LevelObject::Spotlight(*level_coordinates)
+            }
+            LevelObject::Steam(coordinates) => {
+                if self.steams.contains_key(level_coordinates) {
+                    return *object;
                 }
+                let steam = self.steams.remove(coordinates).unwrap();
+                self.steams.insert(*level_coordinates, steam);
+                LevelObject::Steam(*level_coordinates)
+            }
+            LevelObject::Crate(coordinates) => {
+                if self.crates.staticc.contains_key(level_coordinates) {
+                    return *object;
+                }
+                let crate_item = self.crates.staticc.remove(coordinates).unwrap();
+                self.crates.staticc.insert(*level_coordinates, crate_item);
+                LevelObject::Crate(*level_coordinates)
+            }
+            LevelObject::PlayerStart(player) => {
+                let position = if *player == 1 {
+                    &mut self.p1_position
+                } else {
+                    &mut self.p2_position
+                };
+                *position = (
+                    level_coordinates.0 / TILE_SIZE,
+                    level_coordinates.1 / TILE_SIZE,
+                );
+                *object
+            }
+        }
+    }
+
+    // Moves all objects by the same offset. Returns the moved objects in the same order, or the
+    // original ones if any new position is taken or outside of the level
+    pub fn move_objects(
+        &mut self,
+        objects: &[LevelObject],
+        offset: (i64, i64),
+    ) -> Vec<LevelObject> {
+        let level_size = (
+            self.tiles[0].len() as i64 * TILE_SIZE as i64,
+            self.tiles.len() as i64 * TILE_SIZE as i64,
+        );
+        let mut targets = Vec::new();
+        for object in objects {
+            let position = self.get_object_position(object);
+            let target = (position.0 as i64 + offset.0, position.1 as i64 + offset.1);
+            if target.0 < 0 || target.1 < 0 || target.0 >= level_size.0 || target.1 >= level_size.1
+            {
+                return objects.to_vec();
+            }
+            targets.push((target.0 as u32, target.1 as u32));
+        }
+        let moved: HashSet<&LevelObject> = objects.iter().collect();
+        if objects.iter().zip(&targets).any(|(object, target)| {
+            let target_object = object.moved_to(*target);
+            self.has_object(&target_object) && !moved.contains(&target_object)
+        }) {
+            return objects.to_vec();
+        }
+
+        // All objects are removed before inserting so they can take each other's old positions
+        let mut spotlights = Vec::new();
+        let mut steams = Vec::new();
+        let mut crates = Vec::new();
+        for (object, target) in objects.iter().zip(&targets) {
+            match object {
+                LevelObject::Spotlight(coordinates) => {
+                    spotlights.push((*target, self.spotlights.remove(coordinates).unwrap()))
+                }
+                LevelObject::Steam(coordinates) => {
+                    steams.push((*target, self.steams.remove(coordinates).unwrap()))
+                }
+                LevelObject::Crate(coordinates) => {
+                    crates.push((*target, self.crates.staticc.remove(coordinates).unwrap()))
+                }
+                LevelObject::PlayerStart(_) => {
+                    self.move_object(object, target);
+                }
+            }
+        }
+        self.spotlights.extend(spotlights);
+        self.steams.extend(steams);
+        self.crates.staticc.extend(crates);
+        objects
+            .iter()
+            .zip(targets)
+            .map(|(object, target)| object.moved_to(target))
+            .collect()
+    }
+
+    // Spotlights, steams and crates inside the area given in inclusive level coordinates
+    pub fn find_objects_in_area(
+        &self,
+        top_left: &Position,
+        bottom_right: &Position,
+    ) -> Vec<LevelObject> {
+        let inside = |coordinates: &&Position| {
+            coordinates.0 >= top_left.0
+                && coordinates.0 <= bottom_right.0
+                && coordinates.1 >= top_left.1
+                && coordinates.1 <= bottom_right.1
+        };
+        let mut objects: Vec<LevelObject> = self
+            .spotlights
+            .keys()
+            .filter(inside)
+            .map(|coordinates| LevelObject::Spotlight(*coordinates))
+            .collect();
+        objects.extend(
+            self.steams
+                .keys()
+                .filter(inside)
+                .map(|coordinates| LevelObject::Steam(*coordinates)),
+        );
+        objects.extend(
+            self.crates
+                .staticc
+                .keys()
+                .filter(inside)
+                .map(|coordinates| LevelObject::Crate(*coordinates)),
+        );
+        objects
+    }
+
+    pub fn delete_object(&mut self, object: &LevelObject) {
+        match object {
+            LevelObject::Spotlight(coordinates) => {
+                self.spotlights.remove(coordinates);
+            }
+            LevelObject::Steam(coordinates) => {
+                self.steams.remove(coordinates);
+            }
+            LevelObject::Crate(coordinates) => {
+                self.crates.staticc.remove(coordinates);
+            }
+            LevelObject::PlayerStart(_) => {}
+        }
+    }
+
+    pub fn create_shadows(&mut self, rules: &ShadowRules) {
+        for y in 0..self.tiles.len() {
+            for x in 0..self.tiles[y].len() {
+                self.create_shadow(x, y, rules);
             }
         }
     }
 
+    // Shadow of a tile depends on the tiles on its right, top and top right, so a change
+    // can only affect shadows of the changed tiles and tiles on their left, below and
+    // below left.
+    pub fn create_shadows_for_area(&mut self, area: TileArea, rules: &ShadowRules) {
+        let ((x0, y0), (x1, y1)) = area;
+        let y_end = std::cmp::min(y1 as usize + 1, self.tiles.len() - 1);
+        let x_end = std::cmp::min(x1 as usize, self.tiles[0].len() - 1);
+        for y in y0 as usize..=y_end {
+            for x in (x0 as usize).saturating_sub(1)..=x_end {
+                self.create_shadow(x, y, rules);
+            }
+        }
+    }
+
+    fn create_shadow(&mut self, x: usize, y: usize, rules: &ShadowRules) {
+        if self.shadow_locks.contains(&(x as u32, y as u32)) {
+            return;
+        }
+        if self.tiles[y][x].texture_type != TextureType::Walls {
+            let casts_shadow = |x: usize, y: usize| {
+                let tile = &self.tiles[y][x];
+                tile.texture_type == TextureType::Walls && rules.casts_shadow(tile.id)
+            };
+            let on_right = x < self.tiles[y].len() - 1 && casts_shadow(x + 1, y);
+            let on_top_right = y > 0 && x < self.tiles[y].len() - 1 && casts_shadow(x + 1, y - 1);
+            let on_top = y > 0 && casts_shadow(x, y - 1);
+            self.tiles[y][x].shadow = rules.get_shadow(on_top, on_top_right, on_right);
+        } else {
+            self.tiles[y][x].shadow = 0;
+        }
+    }
+
     pub fn serialize(&self) -> Vec<u8> {
         let mut data = Vec::new();
         data.write_u32::<LittleEndian>(VERSION)
@@ -564,16 +936,49 @@ impl Level {
         data
     }
 
-    pub fn origo(&self, render_size: u32) -> (i32, i32) {
-        (
-            -((self.scroll.0 * render_size) as i32),
-            -((self.scroll.1 * render_size) as i32),
-        )
+    pub fn serialize_shadow_locks(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.write_u32::<LittleEndian>(SHADOW_LOCK_VERSION)
+            .expect("Failed to write shadow lock version");
+        data.write_u32::<LittleEndian>(self.shadow_locks.len() as u32)
+            .expect("Failed to write shadow lock amount");
+        // Sorted like in the text format, so that the same locks always give the same file
+        let mut shadow_locks = self.shadow_locks.iter().collect::<Vec<_>>();
+        shadow_locks.sort_by_key(|position| (position.1, position.0));
+        for coordinates in shadow_locks {
+            data.write_u32::<LittleEndian>(coordinates.0)
+                .expect("Failed to write shadow lock x position");
+            data.write_u32::<LittleEndian>(coordinates.1)
+                .expect("Failed to write shadow lock y position");
+        }
+        data
+    }
+
+    pub fn deserialize_shadow_locks(
+        &mut self,
+        mut data: &[u8],
+    ) -> Result<(), DeserializationError> {
+        let version = data.read_u32::<LittleEndian>()?;
+        if version > SHADOW_LOCK_VERSION {
+            return Err(DeserializationError::ContentError(
+                FileTypeError::InvalidVersion,
+            ));
+        }
+        let mut shadow_locks = HashSet::new();
+        let lock_amount = data.read_u32::<LittleEndian>()?;
+        for _ in 0..lock_amount {
+            let x = data.read_u32::<LittleEndian>()?;
+            let y = data.read_u32::<LittleEndian>()?;
+            if (y as usize) < self.tiles.len() && (x as usize) < self.tiles[0].len() {
+                shadow_locks.insert((x, y));
+            }
+        }
+        self.shadow_locks = shadow_locks;
+        Ok(())
     }
 
     pub fn deserialize(mut data: &[u8]) -> Result<Level, DeserializationError> {
         let mut level = Level::get_default_level((32, 22));
-        level.scroll = (0, 0);
         level.spotlights.clear();
         level.steams.clear();
         level.general_info.comment = String::new();
@@ -610,8 +1015,14 @@ impl Level {
         for _ in 0..y_size {
             let mut row = Vec::new();
             for _ in 0..x_size {
+                let texture_type = data.read_u32::<LittleEndian>()?;
+                if texture_type > TextureType::Shadow as u32 {
+                    return Err(DeserializationError::ContentError(
+                        FileTypeError::InvalidTextureType(texture_type),
+                    ));
+                }
                 row.push(Tile {
-                    texture_type: TextureType::from_u32(data.read_u32::<LittleEndian>()?),
+                    texture_type: TextureType::from_u32(texture_type),
                     id: data.read_u32::<LittleEndian>()?,
                     shadow: data.read_u32::<LittleEndian>()?,
                 });
@@ -659,32 +1070,15 @@ impl Level {
 
         level.general_info.time_limit = data.read_u32::<LittleEndian>()?;
 
-        let number_of_enemy_types = if version >= 4 {
-            DIFF_ENEMIES
-        } else {
-            DIFF_ENEMIES - 1
-        } as usize;
+        let (number_of_enemy_types, number_of_weapons, number_of_bullets) =
+            get_table_sizes(version);
         for enemy_number in 0..number_of_enemy_types {
             level.general_info.enemy_table[enemy_number] = data.read_u32::<LittleEndian>()?;
         }
 
-        let number_of_weapons = if version == 1 {
-            DIFF_WEAPONS - 2
-        } else if version == 2 {
-            DIFF_WEAPONS - 1
-        } else {
-            DIFF_WEAPONS
-        } as usize;
         for weapon_number in 0..number_of_weapons {
             level.crates.random.normal.weapons[weapon_number] = data.read_u32::<LittleEndian>()?;
         }
-        let number_of_bullets = if version == 1 {
-            DIFF_BULLETS - 2
-        } else if version == 2 {
-            DIFF_BULLETS - 1
-        } else {
-            DIFF_BULLETS
-        } as usize;
         for bullet_number in 0..number_of_bullets {
             level.crates.random.normal.bullets[bullet_number] = data.read_u32::<LittleEndian>()?;
         }
@@ -700,25 +1094,50 @@ impl Level {
         }
         level.crates.random.deathmatch.energy = data.read_u32::<LittleEndian>()?;
 
-        if version >= 5 {
-            deserialize_crates(data, &mut level.crates.staticc, StaticCrate::Normal)?;
-            deserialize_crates(data, &mut level.crates.staticc, StaticCrate::Deathmatch)?;
+        if version >= STATIC_CRATES_VERSION {
+            deserialize_crates(&mut data, &mut level.crates.staticc, StaticCrate::Normal)?;
+            deserialize_crates(
+                &mut data,
+                &mut level.crates.staticc,
+                StaticCrate::Deathmatch,
+            )?;
         }
 
         Ok(level)
     }
 }
 
+// Number of enemy, random weapon and random bullet values stored by each file version
+pub(crate) fn get_table_sizes(version: u32) -> (usize, usize, usize) {
+    let enemies = if version >= 4 {
+        DIFF_ENEMIES
+    } else {
+        DIFF_ENEMIES - 1
+    };
+    let (weapons, bullets) = match version {
+        1 => (DIFF_WEAPONS - 2, DIFF_BULLETS - 2),
+        2 => (DIFF_WEAPONS - 1, DIFF_BULLETS - 1),
+        _ => (DIFF_WEAPONS, DIFF_BULLETS),
+    };
+    (enemies, weapons, bullets)
+}
+
 fn deserialize_crates(
-    mut data: &[u8],
+    data: &mut &[u8],
     crates: &mut HashMap<Position, StaticCrateType>,
     crate_variant: StaticCrate,
 ) -> Result<(), DeserializationError> {
     let number_of_crates = data.read_u32::<LittleEndian>()?;
     for _crate_index in 0..number_of_crates {
+        let crate_class = data.read_u32::<LittleEndian>()?;
+        if crate_class > CrateClass::Energy as u32 {
+            return Err(DeserializationError::ContentError(
+                FileTypeError::InvalidCrateClass(crate_class),
+            ));
+        }
         let crate_item = StaticCrateType {
             crate_variant,
-            crate_class: CrateClass::from_u32(data.read_u32::<LittleEndian>()?),
+            crate_class: CrateClass::from_u32(crate_class),
             crate_type: data.read_u32::<LittleEndian>()? as u8,
         };
         crates.insert(
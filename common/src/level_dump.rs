@@ -0,0 +1,307 @@
+use crate::level::{
+    bullet_crates, crates, get_crate_index, get_table_sizes, weapon_crates, CrateClass, ALL_CRATES,
+    ENEMY_NAMES, STATIC_CRATES_VERSION, VERSION,
+};
+use std::collections::HashSet;
+
+// Diagnostic decoder for .LEV files. It reads the fields in the same order and with the same
+// version dependent table sizes as Level::deserialize, but keeps going where possible and notes
+// every place where the loaded level differs from the file or loading fails.
+
+pub struct DumpField {
+    pub offset: usize,
+    pub bytes: Vec<u8>,
+    pub name: String,
+    pub value: String,
+    pub problem: Option<String>,
+}
+
+pub struct DumpSection {
+    pub name: &'static str,
+    pub fields: Vec<DumpField>,
+}
+
+pub fn dump_level(data: &[u8]) -> Vec<DumpSection> {
+    let mut decoder = Decoder {
+        data,
+        offset: 0,
+        sections: Vec::new(),
+    };
+    if decoder.decode().is_some() && decoder.offset < data.len() {
+        decoder.section("trailing data");
+        decoder.push(
+            "unread bytes",
+            data.len() - decoder.offset,
+            format!("{} bytes", data.len() - decoder.offset),
+            Some("data after the static crates is ignored by Level::deserialize".to_string()),
+        );
+    }
+    decoder.sections
+}
+
+pub fn get_problems(sections: &[DumpSection]) -> impl Iterator<Item = &DumpField> {
+    sections
+        .iter()
+        .flat_map(|section| &section.fields)
+        .filter(|field| field.problem.is_some())
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    offset: usize,
+    sections: Vec<DumpSection>,
+}
+
+impl<'a> Decoder<'a> {
+    fn section(&mut self, name: &'static str) {
+        self.sections.push(DumpSection {
+            name,
+            fields: Vec::new(),
+        });
+    }
+
+    fn push(&mut self, name: &str, length: usize, value: String, problem: Option<String>) {
+        let bytes = self.data[self.offset..self.offset + length].to_vec();
+        self.sections.last_mut().unwrap().fields.push(DumpField {
+            offset: self.offset,
+            bytes,
+            name: name.to_string(),
+            value,
+            problem,
+        });
+        self.offset += length;
+    }
+
+    // Records the remaining bytes and returns None when the field does not fit in the data, which
+    // is where Level::deserialize fails with an unexpected end of file
+    fn take(&mut self, name: &str, length: usize) -> Option<&'a [u8]> {
+        let remaining = self.data.len() - self.offset;
+        if length > remaining {
+            self.push(
+                name,
+                remaining,
+                "missing".to_string(),
+                Some(format!(
+                    "data ends here, {} bytes needed but {} left",
+                    length, remaining
+                )),
+            );
+            return None;
+        }
+        Some(&self.data[self.offset..self.offset + length])
+    }
+
+    fn read_u32(&mut self, name: &str) -> Option<u32> {
+        let bytes = self.take(name, 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    // Reads a u32 and describes it, with a problem if the loaded level would not have this value
+    fn field(
+        &mut self,
+        name: &str,
+        describe: impl FnOnce(u32) -> (String, Option<String>),
+    ) -> Option<u32> {
+        let value = self.read_u32(name)?;
+        let (description, problem) = describe(value);
+        self.push(name, 4, description, problem);
+        Some(value)
+    }
+
+    fn number(&mut self, name: &str) -> Option<u32> {
+        self.field(name, |value| (value.to_string(), None))
+    }
+
+    // Values which Level::deserialize truncates to a smaller integer type
+    fn truncated(&mut self, name: &str, max: u32) -> Option<u32> {
+        self.field(name, |value| {
+            let problem = (value > max).then(|| {
+                format!(
+                    "does not fit in {} bits and loads as {}",
+                    bits(max),
+                    value & max
+                )
+            });
+            (value.to_string(), problem)
+        })
+    }
+
+    fn decode(&mut self) -> Option<()> {
+        self.section("header");
+        let version = self.field("version", |version| {
+            let problem = (version > VERSION).then(|| {
+                format!(
+                    "newer than version {}, Level::deserialize stops here",
+                    VERSION
+                )
+            });
+            (version.to_string(), problem)
+        })?;
+        let size_problem = |size: u32| {
+            let problem =
+                (size < 1).then(|| "Level::deserialize stops here: invalid size".to_string());
+            (size.to_string(), problem)
+        };
+        let width = self.field("width", size_problem)?;
+        let height = self.field("height", size_problem)?;
+        if version > VERSION || width < 1 || height < 1 {
+            return None;
+        }
+
+        self.section("tiles");
+        for y in 0..height {
+            for x in 0..width {
+                let name = format!("tile {},{}", x, y);
+                let bytes = self.take(&name, 12)?;
+                let [texture_type, id, shadow] =
+                    [0, 4, 8].map(|i| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap()));
+                let (texture, problem) = match texture_type {
+                    0 => ("floor".to_string(), None),
+                    1 => ("walls".to_string(), None),
+                    2 => ("shadow".to_string(), None),
+                    _ => (
+                        format!("texture type {}", texture_type),
+                        Some("Level::deserialize stops here: invalid texture type".to_string()),
+                    ),
+                };
+                self.push(
+                    &name,
+                    12,
+                    format!("{} tile {}, shadow {}", texture, id, shadow),
+                    problem.clone(),
+                );
+                if problem.is_some() {
+                    return None;
+                }
+            }
+        }
+
+        self.section("player starts");
+        for name in ["player 1 x", "player 1 y", "player 2 x", "player 2 y"] {
+            self.number(name)?;
+        }
+
+        self.section("spotlights");
+        let spotlights = self.number("amount")?;
+        let mut positions = HashSet::new();
+        for index in 0..spotlights {
+            let x = self.number(&format!("spotlight {} x", index))?;
+            let y = self.number(&format!("spotlight {} y", index))?;
+            self.mark_duplicate(&mut positions, (x, y), "spotlight");
+            self.truncated(&format!("spotlight {} intensity", index), u8::MAX as u32)?;
+        }
+
+        self.section("steams");
+        let steams = self.number("amount")?;
+        let mut positions = HashSet::new();
+        for index in 0..steams {
+            let x = self.number(&format!("steam {} x", index))?;
+            let y = self.number(&format!("steam {} y", index))?;
+            self.mark_duplicate(&mut positions, (x, y), "steam");
+            self.truncated(&format!("steam {} angle", index), u16::MAX as u32)?;
+            self.truncated(&format!("steam {} range", index), u8::MAX as u32)?;
+        }
+
+        self.section("comment");
+        let bytes = self.take("comment", 20)?;
+        let end = bytes.iter().position(|byte| *byte == 0).unwrap_or(20);
+        let comment = bytes
+            .iter()
+            .filter(|byte| **byte != 0)
+            .map(|byte| *byte as char)
+            .collect::<String>();
+        let problem = bytes[end..]
+            .iter()
+            .any(|byte| *byte != 0)
+            .then(|| "characters after the terminating zero are also loaded".to_string());
+        self.push("comment", 20, format!("{:?}", comment), problem);
+        self.section("time limit");
+        self.number("time limit")?;
+        self.section("enemies");
+        let (enemies, weapons, bullets) = get_table_sizes(version);
+        for name in &ENEMY_NAMES[..enemies] {
+            self.number(name)?;
+        }
+
+        for (section, prefix) in [
+            ("normal random crates", "normal"),
+            ("deathmatch random crates", "deathmatch"),
+        ] {
+            self.section(section);
+            for name in &weapon_crates()[..weapons] {
+                self.number(&format!("{} {}", prefix, name))?;
+            }
+            for name in &bullet_crates()[..bullets] {
+                self.number(&format!("{} {}", prefix, name))?;
+            }
+            self.number(&format!("{} energy", prefix))?;
+        }
+
+        if version >= STATIC_CRATES_VERSION {
+            let mut positions = HashSet::new();
+            for (section, prefix) in [
+                ("normal static crates", "normal crate"),
+                ("deathmatch static crates", "deathmatch crate"),
+            ] {
+                self.section(section);
+                let amount = self.number("amount")?;
+                for index in 0..amount {
+                    self.decode_crate(&format!("{} {}", prefix, index), &mut positions)?;
+                }
+            }
+        }
+        Some(())
+    }
+
+    fn decode_crate(&mut self, name: &str, positions: &mut HashSet<(u32, u32)>) -> Option<()> {
+        let class = self.field(&format!("{} class", name), |class| match class {
+            0 => ("0 (weapon)".to_string(), None),
+            1 => ("1 (bullet)".to_string(), None),
+            2 => ("2 (energy)".to_string(), None),
+            _ => (
+                class.to_string(),
+                Some("Level::deserialize stops here: invalid crate class".to_string()),
+            ),
+        })?;
+        if class > CrateClass::Energy as u32 {
+            return None;
+        }
+        let class = CrateClass::from_u32(class);
+        let count = crates(class).len() as u32;
+        self.field(&format!("{} type", name), |crate_type| {
+            if crate_type < count {
+                let crate_name = ALL_CRATES[get_crate_index(class, crate_type as u8)];
+                (format!("{} ({})", crate_type, crate_name), None)
+            } else {
+                (
+                    crate_type.to_string(),
+                    Some(format!("not one of the {} crates of the class", count)),
+                )
+            }
+        })?;
+        let x = self.number(&format!("{} x", name))?;
+        let y = self.number(&format!("{} y", name))?;
+        self.mark_duplicate(positions, (x, y), "crate");
+        Some(())
+    }
+
+    // Objects are stored by position, so a later object at the same position replaces the earlier
+    fn mark_duplicate(
+        &mut self,
+        positions: &mut HashSet<(u32, u32)>,
+        position: (u32, u32),
+        object: &str,
+    ) {
+        if !positions.insert(position) {
+            let field = self.sections.last_mut().unwrap().fields.last_mut().unwrap();
+            field.problem = Some(format!(
+                "replaces the earlier {} at {},{}",
+                object, position.0, position.1
+            ));
+        }
+    }
+}
+
+fn bits(max: u32) -> u32 {
+    32 - max.leading_zeros()
+}
@@ -0,0 +1,350 @@
+use crate::level::{crates, CrateClass, Level, StaticCrate, StaticCrateType, Steam};
+use crate::types::{TextureType, Tile};
+use std::collections::{HashMap, HashSet};
+
+// Text version of the .LEV format, for reviewing levels in diffs and editing them with scripts.
+// Each line is a keyword followed by space separated values. Tiles are one row per line, each tile
+// written as f<id> for floor or w<id> for walls with an optional :<shadow> suffix:
+//
+//   size 32 22
+//   tiles w0 w1 w1 ... w2
+//   tiles w16 f0 f0:3 ... w16
+//   player 1 1 1
+//   spotlight 100 80 4
+//   steam 300 200 90 3
+//   comment Rust UTK editor
+//   time_limit 60
+//   enemies 1 0 0 0 0 1 0 0
+//   normal_weapons 1 0 0 0 0 0 0 0 0 0 0
+//   crate deathmatch bullet 0 240 260
+//   shadow_lock 5 7
+//
+// Objects are written sorted by position so that the same level always gives the same text.
+
+#[derive(Debug)]
+pub struct LevelTextError {
+    pub line: usize, // 1-based
+    pub message: String,
+}
+
+pub fn format_level(level: &Level) -> String {
+    let mut lines = vec![format!(
+        "size {} {}",
+        level.tiles[0].len(),
+        level.tiles.len()
+    )];
+    for row in &level.tiles {
+        let tiles = row.iter().map(format_tile).collect::<Vec<_>>();
+        lines.push(format!("tiles {}", tiles.join(" ")));
+    }
+    lines.push(format!(
+        "player 1 {} {}",
+        level.p1_position.0, level.p1_position.1
+    ));
+    lines.push(format!(
+        "player 2 {} {}",
+        level.p2_position.0, level.p2_position.1
+    ));
+    for (position, spotlight) in sorted(&level.spotlights) {
+        lines.push(format!(
+            "spotlight {} {} {}",
+            position.0, position.1, spotlight
+        ));
+    }
+    for (position, steam) in sorted(&level.steams) {
+        lines.push(format!(
+            "steam {} {} {} {}",
+            position.0, position.1, steam.angle, steam.range
+        ));
+    }
+    lines.push(format!("comment {}", level.general_info.comment));
+    lines.push(format!("time_limit {}", level.general_info.time_limit));
+    lines.push(format!(
+        "enemies {}",
+        format_values(&level.general_info.enemy_table)
+    ));
+    for (name, set) in [
+        ("normal", &level.crates.random.normal),
+        ("deathmatch", &level.crates.random.deathmatch),
+    ] {
+        lines.push(format!("{}_weapons {}", name, format_values(&set.weapons)));
+        lines.push(format!("{}_bullets {}", name, format_values(&set.bullets)));
+        lines.push(format!("{}_energy {}", name, set.energy));
+    }
+    for (position, crate_item) in sorted(&level.crates.staticc) {
+        lines.push(format!(
+            "crate {} {} {} {} {}",
+            match crate_item.crate_variant {
+                StaticCrate::Normal => "normal",
+                StaticCrate::Deathmatch => "deathmatch",
+            },
+            match crate_item.crate_class {
+                CrateClass::Weapon => "weapon",
+                CrateClass::Bullet => "bullet",
+                CrateClass::Energy => "energy",
+            },
+            crate_item.crate_type,
+            position.0,
+            position.1
+        ));
+    }
+    let mut shadow_locks = level.shadow_locks.iter().collect::<Vec<_>>();
+    shadow_locks.sort_by_key(|position| (position.1, position.0));
+    for position in shadow_locks {
+        lines.push(format!("shadow_lock {} {}", position.0, position.1));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn format_tile(tile: &Tile) -> String {
+    let texture = match tile.texture_type {
+        TextureType::Floor => "f",
+        TextureType::Walls => "w",
+        TextureType::Shadow => "s",
+    };
+    if tile.shadow == 0 {
+        format!("{}{}", texture, tile.id)
+    } else {
+        format!("{}{}:{}", texture, tile.id, tile.shadow)
+    }
+}
+
+fn format_values(values: &[u32]) -> String {
+    values
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn sorted<T>(objects: &HashMap<(u32, u32), T>) -> Vec<(&(u32, u32), &T)> {
+    let mut objects = objects.iter().collect::<Vec<_>>();
+    objects.sort_by_key(|(position, _)| (position.1, position.0));
+    objects
+}
+
+// Missing keywords keep the values of an empty level, except for the size and tiles which are
+// required
+pub fn parse_level(text: &str) -> Result<Level, LevelTextError> {
+    let mut level = Level::get_default_level((3, 3));
+    level.spotlights.clear();
+    level.steams.clear();
+    level.general_info.comment = String::new();
+    level.general_info.enemy_table.fill(0);
+    level.crates.random.normal.weapons.fill(0);
+    level.crates.random.normal.bullets.fill(0);
+    level.crates.random.normal.energy = 0;
+    level.crates.random.deathmatch.weapons.fill(0);
+    level.crates.random.deathmatch.bullets.fill(0);
+    level.crates.random.deathmatch.energy = 0;
+    level.shadow_locks = HashSet::new();
+
+    let mut size = None;
+    let mut tiles = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let error = |message: String| LevelTextError {
+            line: index + 1,
+            message,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        // The comment keeps its spaces, other keywords have space separated values
+        if keyword == "comment" {
+            if rest.len() > 19 || !rest.is_ascii() {
+                return Err(error(format!(
+                    "comment must be at most 19 ASCII characters, got {}",
+                    rest
+                )));
+            }
+            level.general_info.comment = rest.to_string();
+            continue;
+        }
+        let values = rest.split_whitespace().collect::<Vec<_>>();
+        match (keyword, &values[..]) {
+            ("size", [width, height]) => {
+                let width: u32 = parse_number(width).map_err(error)?;
+                let height: u32 = parse_number(height).map_err(error)?;
+                if width < 1 || height < 1 {
+                    return Err(error(format!("invalid level size {}x{}", width, height)));
+                }
+                size = Some((width as usize, height as usize));
+            }
+            ("tiles", row) => {
+                let (width, height) = size.ok_or(error("tiles before size".to_string()))?;
+                if tiles.len() == height {
+                    return Err(error(format!("more than {} rows of tiles", height)));
+                }
+                if row.len() != width {
+                    return Err(error(format!(
+                        "expected {} tiles, got {}",
+                        width,
+                        row.len()
+                    )));
+                }
+                tiles.push(
+                    row.iter()
+                        .map(|tile| parse_tile(tile))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(error)?,
+                );
+            }
+            ("player", [number, x, y]) => {
+                let position = (
+                    parse_number(x).map_err(error)?,
+                    parse_number(y).map_err(error)?,
+                );
+                match *number {
+                    "1" => level.p1_position = position,
+                    "2" => level.p2_position = position,
+                    _ => return Err(error(format!("invalid player {}", number))),
+                }
+            }
+            ("spotlight", [x, y, intensity]) => {
+                level.spotlights.insert(
+                    (
+                        parse_number(x).map_err(error)?,
+                        parse_number(y).map_err(error)?,
+                    ),
+                    parse_number(intensity).map_err(error)?,
+                );
+            }
+            ("steam", [x, y, angle, range]) => {
+                level.steams.insert(
+                    (
+                        parse_number(x).map_err(error)?,
+                        parse_number(y).map_err(error)?,
+                    ),
+                    Steam {
+                        angle: parse_number(angle).map_err(error)?,
+                        range: parse_number(range).map_err(error)?,
+                    },
+                );
+            }
+            ("time_limit", [time_limit]) => {
+                level.general_info.time_limit = parse_number(time_limit).map_err(error)?;
+            }
+            ("enemies", values) => {
+                parse_values(values, &mut level.general_info.enemy_table).map_err(error)?;
+            }
+            ("normal_weapons", values) => {
+                parse_values(values, &mut level.crates.random.normal.weapons).map_err(error)?;
+            }
+            ("normal_bullets", values) => {
+                parse_values(values, &mut level.crates.random.normal.bullets).map_err(error)?;
+            }
+            ("normal_energy", [energy]) => {
+                level.crates.random.normal.energy = parse_number(energy).map_err(error)?;
+            }
+            ("deathmatch_weapons", values) => {
+                parse_values(values, &mut level.crates.random.deathmatch.weapons).map_err(error)?;
+            }
+            ("deathmatch_bullets", values) => {
+                parse_values(values, &mut level.crates.random.deathmatch.bullets).map_err(error)?;
+            }
+            ("deathmatch_energy", [energy]) => {
+                level.crates.random.deathmatch.energy = parse_number(energy).map_err(error)?;
+            }
+            ("crate", [variant, class, crate_type, x, y]) => {
+                let crate_item = parse_crate(variant, class, crate_type).map_err(error)?;
+                level.crates.staticc.insert(
+                    (
+                        parse_number(x).map_err(error)?,
+                        parse_number(y).map_err(error)?,
+                    ),
+                    crate_item,
+                );
+            }
+            ("shadow_lock", [x, y]) => {
+                level.shadow_locks.insert((
+                    parse_number(x).map_err(error)?,
+                    parse_number(y).map_err(error)?,
+                ));
+            }
+            _ => return Err(error(format!("invalid line {}", line))),
+        }
+    }
+
+    match size {
+        Some((_, height)) if tiles.len() == height => {}
+        Some((_, height)) => {
+            return Err(LevelTextError {
+                line: text.lines().count(),
+                message: format!("expected {} rows of tiles, got {}", height, tiles.len()),
+            })
+        }
+        None => {
+            return Err(LevelTextError {
+                line: 1,
+                message: "missing size".to_string(),
+            })
+        }
+    }
+    level.tiles = tiles;
+    let (width, height) = (level.tiles[0].len() as u32, level.tiles.len() as u32);
+    level
+        .shadow_locks
+        .retain(|(x, y)| *x < width && *y < height);
+    Ok(level)
+}
+
+fn parse_number<N: std::str::FromStr>(text: &str) -> Result<N, String> {
+    text.parse().map_err(|_| format!("invalid number {}", text))
+}
+
+fn parse_values(values: &[&str], target: &mut [u32]) -> Result<(), String> {
+    if values.len() != target.len() {
+        return Err(format!(
+            "expected {} values, got {}",
+            target.len(),
+            values.len()
+        ));
+    }
+    for (value, text) in target.iter_mut().zip(values) {
+        *value = parse_number(text)?;
+    }
+    Ok(())
+}
+
+fn parse_tile(text: &str) -> Result<Tile, String> {
+    let (tile, shadow) = match text.split_once(':') {
+        Some((tile, shadow)) => (tile, parse_number(shadow)?),
+        None => (text, 0),
+    };
+    let texture_type = match tile.get(..1) {
+        Some("f") => TextureType::Floor,
+        Some("w") => TextureType::Walls,
+        // format_tile writes s for shadow tiles of .LEV files, which the editor cannot draw
+        _ => return Err(format!("invalid tile {}", text)),
+    };
+    Ok(Tile {
+        texture_type,
+        id: parse_number(&tile[1..])?,
+        shadow,
+    })
+}
+
+fn parse_crate(variant: &str, class: &str, crate_type: &str) -> Result<StaticCrateType, String> {
+    let crate_variant = match variant {
+        "normal" => StaticCrate::Normal,
+        "deathmatch" => StaticCrate::Deathmatch,
+        _ => return Err(format!("invalid crate variant {}", variant)),
+    };
+    let crate_class = match class {
+        "weapon" => CrateClass::Weapon,
+        "bullet" => CrateClass::Bullet,
+        "energy" => CrateClass::Energy,
+        _ => return Err(format!("invalid crate class {}", class)),
+    };
+    let crate_type = parse_number(crate_type)?;
+    if crate_type as usize >= crates(crate_class).len() {
+        return Err(format!("invalid {} crate type {}", class, crate_type));
+    }
+    Ok(StaticCrateType {
+        crate_variant,
+        crate_class,
+        crate_type,
+    })
+}
@@ -17,10 +17,21 @@ pub mod font;
 pub mod general_level_info;
 pub mod graphics;
 pub mod help;
+pub mod inspector;
+pub mod layers;
 pub mod level;
+pub mod level_dump;
+pub mod level_text;
 pub mod load_level;
+pub mod minimap;
 pub mod random_item_editor;
+pub mod recording;
 pub mod render;
+pub mod shadow_rules;
+pub mod software_renderer;
+pub mod status_bar;
+#[cfg(feature = "test-support")]
+pub mod test_support;
 pub mod tile_selector;
 pub mod types;
 pub mod util;
@@ -109,6 +120,12 @@ impl<W: LevelWriter> State<W> {
         }
     }
 
+    // Frontends send Event::Tick only while this is true, so that an idle editor does not wake up
+    // and recordings do not fill with ticks
+    pub fn needs_ticks<L: LevelLister, T: Texture>(&self, context: &Context<L, T>) -> bool {
+        self.mode == Mode::Editor && self.editor.is_animating(context)
+    }
+
     pub fn render<L: LevelLister, R: Renderer>(
         &mut self,
         renderer: &mut R,
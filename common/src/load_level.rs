@@ -1,6 +1,6 @@
 use crate::context::Context;
-use crate::event::{Event, Keycode};
-use crate::level::Level;
+use crate::event::{wheel_to_key_down, Event, Keycode};
+use crate::level::{Level, SHADOW_LOCK_EXTENSION};
 use crate::render::{Renderer, Texture};
 use crate::types::*;
 use crate::util::{get_bottom_text_position, get_title_position};
@@ -12,6 +12,7 @@ pub trait LevelLister {
     fn len(&self) -> usize;
     fn level_name(&self, index: usize) -> &str;
     fn load_level(&self, index: usize) -> Vec<u8>;
+    fn load_sidecar(&self, index: usize, extension: &str) -> Option<Vec<u8>>;
 }
 
 pub struct LoadLevelState {
@@ -45,6 +46,7 @@ impl LoadLevelState {
             Event::Quit { .. }
             | Event::KeyDown {
                 keycode: Keycode::Escape,
+                ..
             } => {
                 context.level_lister.reset();
                 return EventResult::ChangeMode(Mode::Editor);
@@ -52,6 +54,9 @@ impl LoadLevelState {
             Event::Window { .. } => {
                 return EventResult::ChangeMode(Mode::Editor);
             }
+            Event::MouseWheel { y, .. } if y != 0 => {
+                return self.handle_event(context, wheel_to_key_down(y));
+            }
             Event::KeyDown { keycode, .. } => match keycode {
                 Keycode::Down => {
                     if self.selected < context.level_lister.len() - 1 {
@@ -66,8 +71,17 @@ impl LoadLevelState {
                 Keycode::Return | Keycode::KpEnter => {
                     if context.level_lister.len() > 0 {
                         let level_data = context.level_lister.load_level(self.selected);
-                        if let Ok(level) = Level::deserialize(&level_data) {
+                        if let Ok(mut level) = Level::deserialize(&level_data) {
+                            if let Some(data) = context
+                                .level_lister
+                                .load_sidecar(self.selected, SHADOW_LOCK_EXTENSION)
+                            {
+                                // Level is still usable without its manual shadows
+                                let _ = level.deserialize_shadow_locks(&data);
+                            }
                             context.level = level;
+                            context.viewport = (0, 0);
+                            context.unsaved_changes = false;
                             let level_name =
                                 context.level_lister.level_name(self.selected).to_string();
                             context.saved_level_name = Some(level_name.clone());
@@ -0,0 +1,201 @@
+use crate::context::Context;
+use crate::font::Font;
+use crate::graphics::Graphics;
+use crate::level::{Level, StaticCrate, TILE_SIZE};
+use crate::load_level::LevelLister;
+use crate::render::{Rect, Renderer, RendererColor, Texture};
+use crate::status_bar;
+use crate::types::TextureType;
+
+const MAX_WIDTH: u32 = 160;
+const MAX_HEIGHT: u32 = 120;
+
+// Overview of the whole level in the bottom right corner. Clicking or dragging on it centers
+// the viewport to the pointed position.
+#[derive(Default)]
+pub struct Minimap {
+    visible: bool,
+    dragging: bool,
+}
+
+struct MinimapLayout {
+    x: u32,
+    y: u32,
+    tile_size: u32,
+    width: u32,
+    height: u32,
+}
+
+impl MinimapLayout {
+    fn new<T>(font: &Font<T>, graphics: &Graphics, level: &Level) -> Self {
+        let tiles = (level.tiles[0].len() as u32, level.tiles.len() as u32);
+        let tile_size = std::cmp::max(
+            std::cmp::min(font.px(MAX_WIDTH) / tiles.0, font.px(MAX_HEIGHT) / tiles.1),
+            1,
+        );
+        let width = tiles.0 * tile_size;
+        let height = tiles.1 * tile_size;
+        MinimapLayout {
+            x: graphics.resolution_x - width - font.px(4),
+            y: graphics.resolution_y - height - status_bar::get_height(font) - font.px(4),
+            tile_size,
+            width,
+            height,
+        }
+    }
+
+    fn contains(&self, point: &(u32, u32)) -> bool {
+        point.0 >= self.x
+            && point.0 < self.x + self.width
+            && point.1 >= self.y
+            && point.1 < self.y + self.height
+    }
+
+    fn get_level_coordinates(&self, point: &(u32, u32)) -> (u32, u32) {
+        let x = point.0.clamp(self.x, self.x + self.width - 1) - self.x;
+        let y = point.1.clamp(self.y, self.y + self.height - 1) - self.y;
+        (
+            x * TILE_SIZE / self.tile_size,
+            y * TILE_SIZE / self.tile_size,
+        )
+    }
+
+    fn get_rect(&self, level_coordinates: &(u32, u32), size: (u32, u32)) -> Rect {
+        Rect::new(
+            (self.x + level_coordinates.0 * self.tile_size / TILE_SIZE) as i32,
+            (self.y + level_coordinates.1 * self.tile_size / TILE_SIZE) as i32,
+            size.0,
+            size.1,
+        )
+    }
+}
+
+impl Minimap {
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.dragging = false;
+    }
+
+    // Level coordinates to center the viewport to, if the click hit the minimap
+    pub fn handle_mouse_down<L: LevelLister, T: Texture>(
+        &mut self,
+        context: &Context<L, T>,
+    ) -> Option<(u32, u32)> {
+        if !self.visible {
+            return None;
+        }
+        let layout = MinimapLayout::new(&context.font, &context.graphics, &context.level);
+        if !layout.contains(&context.mouse) {
+            return None;
+        }
+        self.dragging = true;
+        Some(layout.get_level_coordinates(&context.mouse))
+    }
+
+    pub fn handle_mouse_motion<L: LevelLister, T: Texture>(
+        &self,
+        context: &Context<L, T>,
+    ) -> Option<(u32, u32)> {
+        if !self.dragging {
+            return None;
+        }
+        let layout = MinimapLayout::new(&context.font, &context.graphics, &context.level);
+        Some(layout.get_level_coordinates(&context.mouse))
+    }
+
+    // Returns true if the button release ended a drag started on the minimap
+    pub fn handle_mouse_up(&mut self) -> bool {
+        std::mem::take(&mut self.dragging)
+    }
+
+    pub fn render<L: LevelLister, R: Renderer>(
+        &self,
+        renderer: &mut R,
+        context: &Context<L, R::Texture>,
+    ) {
+        if !self.visible {
+            return;
+        }
+        let level = &context.level;
+        let graphics = &context.graphics;
+        let layout = MinimapLayout::new(&context.font, graphics, level);
+        let tile_size = layout.tile_size;
+        renderer.draw_rect(
+            &Rect::new(
+                layout.x as i32 - 1,
+                layout.y as i32 - 1,
+                layout.width + 2,
+                layout.height + 2,
+            ),
+            RendererColor::White,
+        );
+        for (y, row) in level.tiles.iter().enumerate() {
+            // Consecutive tiles of the same type are drawn as one rectangle
+            let mut start = 0;
+            for x in 1..=row.len() {
+                if x < row.len() && row[x].texture_type == row[start].texture_type {
+                    continue;
+                }
+                let color = match row[start].texture_type {
+                    TextureType::Walls => RendererColor::LightGrey,
+                    _ => RendererColor::DarkGrey,
+                };
+                renderer.fill_rect(
+                    &layout.get_rect(
+                        &(start as u32 * TILE_SIZE, y as u32 * TILE_SIZE),
+                        ((x - start) as u32 * tile_size, tile_size),
+                    ),
+                    color,
+                );
+                start = x;
+            }
+        }
+        let object_size = (std::cmp::max(tile_size, 2), std::cmp::max(tile_size, 2));
+        for coordinates in level.spotlights.keys() {
+            renderer.fill_rect(
+                &layout.get_rect(coordinates, object_size),
+                RendererColor::Blue,
+            );
+        }
+        for coordinates in level.steams.keys() {
+            renderer.fill_rect(
+                &layout.get_rect(coordinates, object_size),
+                RendererColor::Red,
+            );
+        }
+        for (coordinates, crate_item) in &level.crates.staticc {
+            let color = match crate_item.crate_variant {
+                StaticCrate::Normal => RendererColor::LightGreen,
+                StaticCrate::Deathmatch => RendererColor::LightBlue,
+            };
+            renderer.fill_rect(&layout.get_rect(coordinates, object_size), color);
+        }
+        for position in [level.p1_position, level.p2_position] {
+            renderer.fill_rect(
+                &layout.get_rect(
+                    &(position.0 * TILE_SIZE, position.1 * TILE_SIZE),
+                    object_size,
+                ),
+                RendererColor::Yellow,
+            );
+        }
+        let view_size = status_bar::get_level_view_size(graphics, &context.font);
+        let viewport_size = (
+            view_size.0 * tile_size / TILE_SIZE,
+            view_size.1 * tile_size / TILE_SIZE,
+        );
+        let viewport = layout.get_rect(&context.viewport, (0, 0));
+        renderer.draw_rect(
+            &Rect::new(
+                viewport.x,
+                viewport.y,
+                std::cmp::min(viewport_size.0, layout.x + layout.width - viewport.x as u32),
+                std::cmp::min(
+                    viewport_size.1,
+                    layout.y + layout.height - viewport.y as u32,
+                ),
+            ),
+            RendererColor::White,
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use crate::context::Context;
-use crate::event::{Event, Keycode};
+use crate::event::{wheel_to_key_down, Event, Keycode};
 use crate::level::Level;
 use crate::level::ALL_CRATES;
 use crate::load_level::LevelLister;
@@ -66,6 +66,7 @@ impl RandomItemEditorState {
             Event::Quit { .. }
             | Event::KeyDown {
                 keycode: Keycode::Escape,
+                ..
             } => {
                 text_input.stop();
                 return EventResult::ChangeMode(Mode::Editor);
@@ -73,6 +74,9 @@ impl RandomItemEditorState {
             Event::Window { .. } => {
                 return EventResult::ChangeMode(Mode::Editor);
             }
+            Event::MouseWheel { y, .. } if y != 0 => {
+                return self.handle_event(context, text_input, game_type, wheel_to_key_down(y));
+            }
             Event::KeyDown { keycode, .. } => match keycode {
                 Keycode::Down => {
                     if self.selected < ALL_CRATES.len() - 1 {
@@ -87,11 +91,13 @@ impl RandomItemEditorState {
                 Keycode::Right => {
                     let value = get_value(&context.level, &game_type, self.selected);
                     set_value(&mut context.level, &game_type, self.selected, value + 1);
+                    context.unsaved_changes = true;
                 }
                 Keycode::Left => {
                     let value = get_value(&context.level, &game_type, self.selected);
                     if value > 0 {
                         set_value(&mut context.level, &game_type, self.selected, value - 1);
+                        context.unsaved_changes = true;
                     }
                 }
                 _ => return EventResult::EventIgnored,
@@ -0,0 +1,251 @@
+use crate::event::{Event, Keycode, Modifiers, MouseButton, WindowEvent, ALL_KEYCODES};
+use std::fmt::Write;
+
+// Recordings are text with the screen size and UI scale on the first line, followed by one event
+// per line prefixed with the milliseconds since the recording started:
+//
+//   screen 1280 800 2
+//   0 mouse_motion 100 200
+//   530 key_down S ctrl
+//   900 text_input level 1
+pub struct Recording {
+    pub screen: (u32, u32),
+    pub render_multiplier: u32,
+    pub events: Vec<(u64, Event)>,
+}
+
+#[derive(Debug)]
+pub struct RecordingError {
+    pub line: usize, // 1-based
+    pub message: String,
+}
+
+impl Recording {
+    pub fn parse(text: &str) -> Result<Recording, RecordingError> {
+        let mut lines = text.lines().enumerate();
+        let screen = lines
+            .next()
+            .and_then(|(_, line)| line.strip_prefix("screen "))
+            .map(|values| values.split(' ').map(str::parse).collect::<Vec<_>>());
+        let (screen, render_multiplier) = match screen.as_deref() {
+            Some([Ok(width), Ok(height), Ok(render_multiplier)]) => {
+                ((*width, *height), *render_multiplier)
+            }
+            _ => {
+                return Err(RecordingError {
+                    line: 1,
+                    message: "expected screen <width> <height> <scale>".to_string(),
+                })
+            }
+        };
+        let events = lines
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(index, line)| {
+                parse_line(line).map_err(|message| RecordingError {
+                    line: index + 1,
+                    message,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Recording {
+            screen,
+            render_multiplier,
+            events,
+        })
+    }
+}
+
+pub fn format_header(screen: (u32, u32), render_multiplier: u32) -> String {
+    format!("screen {} {} {}\n", screen.0, screen.1, render_multiplier)
+}
+
+pub fn format_line(milliseconds: u64, event: &Event) -> String {
+    format!("{} {}\n", milliseconds, format_event(event))
+}
+
+pub fn format_event(event: &Event) -> String {
+    match event {
+        Event::Quit => "quit".to_string(),
+        Event::Window {
+            win_event: WindowEvent::Resized { width, height },
+        } => format!("window_resized {} {}", width, height),
+        Event::Window {
+            win_event: WindowEvent::Maximized,
+        } => "window_maximized".to_string(),
+        Event::KeyDown { keycode, modifiers } => {
+            with_modifiers(format!("key_down {:?}", keycode), modifiers)
+        }
+        Event::MouseButtonDown { button, modifiers } => with_modifiers(
+            format!("mouse_down {}", format_mouse_button(button)),
+            modifiers,
+        ),
+        Event::MouseButtonUp { button } => format!("mouse_up {}", format_mouse_button(button)),
+        Event::MouseMotion { x, y } => format!("mouse_motion {} {}", x, y),
+        Event::MouseWheel { x, y, modifiers } => {
+            with_modifiers(format!("mouse_wheel {} {}", x, y), modifiers)
+        }
+        Event::TextInput { text } => format!("text_input {}", text),
+        Event::FileUpload { name, data } => {
+            // The name goes last so that it can have spaces, - stands for no data
+            let mut line = "file_upload ".to_string();
+            if data.is_empty() {
+                line.push('-');
+            }
+            for byte in data {
+                write!(line, "{:02x}", byte).unwrap();
+            }
+            write!(line, " {}", name).unwrap();
+            line
+        }
+        Event::Tick { milliseconds } => format!("tick {}", milliseconds),
+    }
+}
+
+fn format_mouse_button(button: &MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "left",
+        MouseButton::Middle => "middle",
+        MouseButton::Right => "right",
+    }
+}
+
+fn with_modifiers(mut text: String, modifiers: &Modifiers) -> String {
+    let names = [
+        (modifiers.shift, "shift"),
+        (modifiers.ctrl, "ctrl"),
+        (modifiers.alt, "alt"),
+    ]
+    .iter()
+    .filter(|(pressed, _)| *pressed)
+    .map(|(_, name)| *name)
+    .collect::<Vec<_>>();
+    if !names.is_empty() {
+        text.push(' ');
+        text.push_str(&names.join("+"));
+    }
+    text
+}
+
+fn parse_line(line: &str) -> Result<(u64, Event), String> {
+    let (milliseconds, event) = line
+        .split_once(' ')
+        .ok_or(format!("expected <milliseconds> <event>, got {}", line))?;
+    let milliseconds = milliseconds
+        .parse()
+        .map_err(|_| format!("invalid milliseconds {}", milliseconds))?;
+    Ok((milliseconds, parse_event(event)?))
+}
+
+pub fn parse_event(text: &str) -> Result<Event, String> {
+    let (name, arguments) = text.split_once(' ').unwrap_or((text, ""));
+    // Text input and uploaded file names keep their spaces, other events have space separated
+    // arguments
+    if name == "text_input" {
+        return Ok(Event::TextInput {
+            text: arguments.to_string(),
+        });
+    }
+    if name == "file_upload" {
+        let (data, name) = arguments
+            .split_once(' ')
+            .ok_or(format!("invalid event {}", text))?;
+        return Ok(Event::FileUpload {
+            name: name.to_string(),
+            data: if data == "-" {
+                Vec::new()
+            } else {
+                parse_hex(data)?
+            },
+        });
+    }
+    let arguments = arguments.split_whitespace().collect::<Vec<_>>();
+    let event = match (name, &arguments[..]) {
+        ("quit", []) => Event::Quit,
+        ("window_resized", [width, height]) => Event::Window {
+            win_event: WindowEvent::Resized {
+                width: parse_number(width)?,
+                height: parse_number(height)?,
+            },
+        },
+        ("window_maximized", []) => Event::Window {
+            win_event: WindowEvent::Maximized,
+        },
+        ("key_down", [keycode, modifiers @ ..]) => Event::KeyDown {
+            keycode: parse_keycode(keycode)?,
+            modifiers: parse_modifiers(modifiers)?,
+        },
+        ("mouse_down", [button, modifiers @ ..]) => Event::MouseButtonDown {
+            button: parse_mouse_button(button)?,
+            modifiers: parse_modifiers(modifiers)?,
+        },
+        ("mouse_up", [button]) => Event::MouseButtonUp {
+            button: parse_mouse_button(button)?,
+        },
+        ("mouse_motion", [x, y]) => Event::MouseMotion {
+            x: parse_number(x)?,
+            y: parse_number(y)?,
+        },
+        ("mouse_wheel", [x, y, modifiers @ ..]) => Event::MouseWheel {
+            x: parse_number(x)?,
+            y: parse_number(y)?,
+            modifiers: parse_modifiers(modifiers)?,
+        },
+        ("tick", [milliseconds]) => Event::Tick {
+            milliseconds: parse_number(milliseconds)?,
+        },
+        _ => return Err(format!("invalid event {}", text)),
+    };
+    Ok(event)
+}
+
+fn parse_number<N: std::str::FromStr>(text: &str) -> Result<N, String> {
+    text.parse().map_err(|_| format!("invalid number {}", text))
+}
+
+fn parse_keycode(text: &str) -> Result<Keycode, String> {
+    ALL_KEYCODES
+        .into_iter()
+        .find(|keycode| format!("{:?}", keycode) == text)
+        .ok_or(format!("invalid key {}", text))
+}
+
+fn parse_mouse_button(text: &str) -> Result<MouseButton, String> {
+    match text {
+        "left" => Ok(MouseButton::Left),
+        "middle" => Ok(MouseButton::Middle),
+        "right" => Ok(MouseButton::Right),
+        _ => Err(format!("invalid mouse button {}", text)),
+    }
+}
+
+fn parse_modifiers(arguments: &[&str]) -> Result<Modifiers, String> {
+    let mut modifiers = Modifiers::default();
+    match arguments {
+        [] => {}
+        [names] => {
+            for name in names.split('+') {
+                match name {
+                    "shift" => modifiers.shift = true,
+                    "ctrl" => modifiers.ctrl = true,
+                    "alt" => modifiers.alt = true,
+                    _ => return Err(format!("invalid modifier {}", name)),
+                }
+            }
+        }
+        _ => return Err(format!("invalid modifiers {}", arguments.join(" "))),
+    }
+    Ok(modifiers)
+}
+
+fn parse_hex(text: &str) -> Result<Vec<u8>, String> {
+    if !text.len().is_multiple_of(2) {
+        return Err(format!("invalid hex data {}", text));
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&text[i..i + 2], 16)
+                .map_err(|_| format!("invalid hex data {}", text))
+        })
+        .collect()
+}
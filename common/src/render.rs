@@ -1,7 +1,18 @@
+use crate::fn2::FN2;
 use crate::graphics::Graphics;
-use crate::level::TILE_SIZE;
+use crate::level::{
+    get_crate_index, CrateClass, StaticCrate, StaticCrateType, ALL_CRATES, TILE_SIZE,
+};
 use crate::util::get_tile_coordinates;
 
+const LIGHT_TEXTURE_SIZE: u32 = 64;
+pub const CRATE_ICON_SIZE: u32 = 24;
+// Icon labels in ALL_CRATES order
+const CRATE_ICON_LABELS: [&str; 21] = [
+    "PI", "SG", "UZ", "AR", "GL", "AG", "HL", "AS", "C4", "FT", "MD", "9", "12", "SS", "LG", "MG",
+    "HG", "C4", "GA", "MI", "E",
+];
+
 #[derive(Clone, Copy)]
 pub enum RendererColor {
     Black,
@@ -11,6 +22,8 @@ pub enum RendererColor {
     LightBlue,
     LightGreen,
     LightGrey,
+    DarkGrey,
+    Yellow,
 }
 
 impl RendererColor {
@@ -23,6 +36,8 @@ impl RendererColor {
             RendererColor::LightBlue => Color::from((100, 100, 255)),
             RendererColor::LightGreen => Color::from((100, 255, 100)),
             RendererColor::LightGrey => Color::from((200, 200, 200)),
+            RendererColor::DarkGrey => Color::from((80, 80, 80)),
+            RendererColor::Yellow => Color::from((255, 255, 0)),
         }
     }
 }
@@ -122,9 +137,109 @@ pub trait Renderer {
     fn fill_rect(&mut self, rect: &Rect, color: RendererColor);
     fn draw_circle(&mut self, center: Point, radius: u32, color: RendererColor);
     fn render_texture(&mut self, texture: &Self::Texture, src: Option<Rect>, dst: Rect);
+    // Blends the color over the area according to the color alpha
+    fn fill_rect_blended(&mut self, rect: &Rect, color: Color);
+    // Adds the texture colors, weighted by their alpha and strength (0-255), to the screen
+    fn render_texture_additive(&mut self, texture: &Self::Texture, dst: Rect, strength: u8);
     fn window_size(&self) -> (u32, u32);
 }
 
+// White circle fading out from the center, for additive lighting
+pub fn create_light_texture<R: Renderer>(renderer: &mut R) -> R::Texture {
+    let size = LIGHT_TEXTURE_SIZE;
+    let radius = size as f32 / 2.0;
+    let mut pixels = Vec::with_capacity((size * size) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let dx = x as f32 + 0.5 - radius;
+            let dy = y as f32 + 0.5 - radius;
+            let falloff = (1.0 - (dx * dx + dy * dy).sqrt() / radius).max(0.0);
+            pixels.push(Color::from((
+                255,
+                255,
+                255,
+                (falloff * falloff * 255.0) as u8,
+            )));
+        }
+    }
+    renderer.create_texture(size, size, &pixels)
+}
+
+// Sprite sheet with one icon per crate in ALL_CRATES order, normal game crates on the first row
+// and deathmatch crates on the second
+pub fn create_crate_icons<R: Renderer>(renderer: &mut R, fn2: &FN2) -> R::Texture {
+    let size = CRATE_ICON_SIZE;
+    let width = size * ALL_CRATES.len() as u32;
+    let mut pixels = vec![Color::from((0, 0, 0, 0)); (width * size * 2) as usize];
+    for (index, label) in CRATE_ICON_LABELS.iter().enumerate() {
+        let class_color: (u8, u8, u8) = if index < get_crate_index(CrateClass::Bullet, 0) {
+            (150, 70, 30)
+        } else if index < get_crate_index(CrateClass::Energy, 0) {
+            (130, 120, 30)
+        } else {
+            (30, 100, 150)
+        };
+        let dark_color = (class_color.0 / 2, class_color.1 / 2, class_color.2 / 2);
+        let light_color = (
+            class_color.0.saturating_add(90),
+            class_color.1.saturating_add(90),
+            class_color.2.saturating_add(90),
+        );
+        for variant in [StaticCrate::Normal, StaticCrate::Deathmatch] {
+            // Deathmatch icons are drawn inverted: dark body, colored border and label
+            let (body, border, border_width, text) = match variant {
+                StaticCrate::Normal => (class_color, dark_color, 1, (255, 255, 255)),
+                StaticCrate::Deathmatch => ((30, 30, 30), class_color, 2, light_color),
+            };
+            let origin_x = index as u32 * size;
+            let origin_y = variant as u32 * size;
+            let mut put = |x: u32, y: u32, color: (u8, u8, u8)| {
+                if x < size && y < size {
+                    pixels[((origin_y + y) * width + origin_x + x) as usize] =
+                        Color::from((color.0, color.1, color.2, 255));
+                }
+            };
+            for y in 0..size {
+                for x in 0..size {
+                    let edge = x.min(y).min(size - 1 - x).min(size - 1 - y);
+                    put(x, y, if edge < border_width { border } else { body });
+                }
+            }
+
+            let characters = label
+                .chars()
+                .map(|c| &fn2.characters[c as usize - fn2.first_visible_character as usize])
+                .collect::<Vec<_>>();
+            let text_width = characters.iter().map(|c| c.width + 1).sum::<u32>() - 1;
+            let text_height = characters.iter().map(|c| c.height).max().unwrap_or(0);
+            // Shadow first so that it does not cover the neighbouring character
+            for (offset, color) in [(1, (0, 0, 0)), (0, text)] {
+                let mut text_x = (size - text_width) / 2 + offset;
+                let text_y = (size - text_height) / 2 + offset;
+                for character in &characters {
+                    for line in &character.lines {
+                        for x in line.x..line.x + line.width {
+                            put(text_x + x as u32, text_y + line.y as u32, color);
+                        }
+                    }
+                    text_x += character.width + 1;
+                }
+            }
+        }
+    }
+    renderer.create_texture(width, size * 2, &pixels)
+}
+
+pub fn get_crate_icon_rect(crate_item: &StaticCrateType) -> Rect {
+    let index = get_crate_index(crate_item.crate_class, crate_item.crate_type) as u32;
+    Rect::new(
+        (index * CRATE_ICON_SIZE) as i32,
+        (crate_item.crate_variant as u32 * CRATE_ICON_SIZE) as i32,
+        CRATE_ICON_SIZE,
+        CRATE_ICON_SIZE,
+    )
+}
+
 pub fn get_texture_rect<T: Texture>(texture: &T, render_multiplier: u32) -> Rect {
     let (width, height) = get_texture_render_size(texture, render_multiplier);
     Rect::new(0, 0, width, height)
@@ -142,11 +257,10 @@ pub fn highlight_selected_tile<R: Renderer>(
     color: RendererColor,
 ) {
     let render_size = graphics.get_render_size();
-    let render_multiplier = graphics.render_multiplier;
     let (x_logical, y_logical) =
         get_tile_coordinates(id, graphics.get_x_tiles_per_screen() * TILE_SIZE);
-    let x = x_logical * render_multiplier;
-    let y = y_logical * render_multiplier;
+    let x = x_logical / TILE_SIZE * render_size;
+    let y = y_logical / TILE_SIZE * render_size;
     renderer.draw_rect(
         &Rect::new(x as i32, y as i32, render_size, render_size),
         color,
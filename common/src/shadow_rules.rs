@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+
+const TOP: usize = 1;
+const TOP_RIGHT: usize = 2;
+const RIGHT: usize = 4;
+
+pub struct ShadowRules {
+    casters: Option<HashSet<u32>>, // wall ids casting shadows, None if all walls do
+    shadows: [u32; 8], // SHADOWS_ALPHA id + 1 (0 for none) for each TOP/TOP_RIGHT/RIGHT pattern
+}
+
+#[derive(Debug)]
+pub enum ShadowRulesError {
+    InvalidLine(usize),
+    InvalidShadow(u32), // shadow which is not in the tiles of SHADOWS_ALPHA
+}
+
+impl Default for ShadowRules {
+    fn default() -> Self {
+        let mut shadows = [0; 8];
+        for (pattern, shadow) in shadows.iter_mut().enumerate() {
+            *shadow = if pattern & TOP_RIGHT != 0 || pattern & (TOP | RIGHT) == TOP | RIGHT {
+                1
+            } else if pattern & TOP != 0 {
+                3
+            } else if pattern & RIGHT != 0 {
+                2
+            } else {
+                0
+            };
+        }
+        ShadowRules {
+            casters: None,
+            shadows,
+        }
+    }
+}
+
+impl ShadowRules {
+    // Rules not mentioned in the text keep their default values. Example:
+    //
+    //   # wall ids casting shadows: "all" or a list of ids and ranges
+    //   casters 0-15 32 33
+    //   # shadow <top><top right><right> <SHADOWS_ALPHA id + 1, 0 for none>
+    //   shadow 100 3
+    pub fn parse(text: &str) -> Result<ShadowRules, ShadowRulesError> {
+        let mut rules = ShadowRules::default();
+        for (index, line) in text.lines().enumerate() {
+            let invalid_line = ShadowRulesError::InvalidLine(index + 1);
+            let line = line.split('#').next().unwrap().trim();
+            let mut words = line.split_whitespace();
+            match words.next() {
+                None => {}
+                Some("casters") => {
+                    let words: Vec<&str> = words.collect();
+                    rules.casters = if words == ["all"] {
+                        None
+                    } else {
+                        let mut casters = HashSet::new();
+                        for word in words {
+                            let (first, last) = word.split_once('-').unwrap_or((word, word));
+                            match (first.parse::<u32>(), last.parse::<u32>()) {
+                                (Ok(first), Ok(last)) if first <= last => {
+                                    casters.extend(first..=last)
+                                }
+                                _ => return Err(invalid_line),
+                            }
+                        }
+                        Some(casters)
+                    };
+                }
+                Some("shadow") => {
+                    let (pattern, shadow) = match (words.next(), words.next(), words.next()) {
+                        (Some(pattern), Some(shadow), None) => (pattern, shadow),
+                        _ => return Err(invalid_line),
+                    };
+                    let pattern = parse_pattern(pattern).ok_or(invalid_line)?;
+                    rules.shadows[pattern] = shadow
+                        .parse()
+                        .map_err(|_| ShadowRulesError::InvalidLine(index + 1))?;
+                }
+                Some(_) => return Err(invalid_line),
+            }
+        }
+        Ok(rules)
+    }
+
+    // Shadow tilesets differ between mods, so the shadows are checked once the texture is loaded
+    pub fn check_shadows(self, shadow_tiles: u32) -> Result<ShadowRules, ShadowRulesError> {
+        match self.shadows.iter().find(|shadow| **shadow > shadow_tiles) {
+            Some(shadow) => Err(ShadowRulesError::InvalidShadow(*shadow)),
+            None => Ok(self),
+        }
+    }
+
+    pub fn casts_shadow(&self, wall_id: u32) -> bool {
+        match &self.casters {
+            Some(casters) => casters.contains(&wall_id),
+            None => true,
+        }
+    }
+
+    pub fn get_shadow(&self, on_top: bool, on_top_right: bool, on_right: bool) -> u32 {
+        let mut pattern = 0;
+        if on_top {
+            pattern |= TOP;
+        }
+        if on_top_right {
+            pattern |= TOP_RIGHT;
+        }
+        if on_right {
+            pattern |= RIGHT;
+        }
+        self.shadows[pattern]
+    }
+}
+
+fn parse_pattern(text: &str) -> Option<usize> {
+    let bits: Vec<char> = text.chars().collect();
+    if bits.len() != 3 || !bits.iter().all(|bit| *bit == '0' || *bit == '1') {
+        return None;
+    }
+    Some(
+        [TOP, TOP_RIGHT, RIGHT]
+            .iter()
+            .zip(bits)
+            .filter(|(_, bit)| *bit == '1')
+            .map(|(flag, _)| flag)
+            .sum(),
+    )
+}
@@ -1,29 +1,31 @@
-use common::render::{Color, Point, Rect, Renderer, RendererColor, Texture};
+use crate::render::{Color, Point, Rect, Renderer, RendererColor, Texture};
 
-pub struct CanvasTexture {
+pub struct SoftwareTexture {
     width: u32,
     height: u32,
     pixels: Vec<u32>,
 }
 
-impl Texture for CanvasTexture {
+impl Texture for SoftwareTexture {
     fn size(&self) -> (u32, u32) {
         (self.width, self.height)
     }
 }
 
-pub struct CanvasRenderer {
+// Renders to memory on the CPU, for frontends without a hardware renderer and for writing
+// images without a window
+pub struct SoftwareRenderer {
     width: u32,
     height: u32,
     screen: Vec<u32>,
 }
 
-impl CanvasRenderer {
-    pub fn new(width: u32, height: u32) -> CanvasRenderer {
-        CanvasRenderer {
+impl SoftwareRenderer {
+    pub fn new(width: u32, height: u32) -> SoftwareRenderer {
+        SoftwareRenderer {
             width,
             height,
-            screen: vec![0; (width * height * 4) as usize],
+            screen: vec![0; (width * height) as usize],
         }
     }
 
@@ -35,23 +37,18 @@ impl CanvasRenderer {
         self.height
     }
 
-    pub fn pixels(&self) -> *const u32 {
-        self.screen.as_ptr()
+    // Pixels in rows from the top left corner, with the color channels in RGBA byte order
+    pub fn pixels(&self) -> &[u32] {
+        &self.screen
     }
 
-    pub fn create_texture_rgba(&self, width: u32, height: u32, data: &[u8]) -> CanvasTexture {
-        CanvasTexture {
+    pub fn create_texture_rgba(&self, width: u32, height: u32, data: &[u8]) -> SoftwareTexture {
+        SoftwareTexture {
             width,
             height,
             pixels: data
                 .chunks(4)
-                .map(|c| {
-                    let r = c[0] as u32;
-                    let g = c[1] as u32;
-                    let b = c[2] as u32;
-                    let a = c[3] as u32;
-                    (a << 24) | (b << 16) | (g << 8) | r
-                })
+                .map(|c| Color::from((c[0], c[1], c[2], c[3])).to_u32())
                 .collect(),
         }
     }
@@ -85,7 +82,7 @@ impl CanvasRenderer {
             return;
         }
         if y1 > y2 {
-            return self.draw_horizontal_line(x, y2, y1, color);
+            return self.draw_vertical_line(x, y2, y1, color);
         }
         if y1 >= height || y2 < 0 {
             return;
@@ -99,15 +96,15 @@ impl CanvasRenderer {
     }
 }
 
-impl Renderer for CanvasRenderer {
-    type Texture = CanvasTexture;
+impl Renderer for SoftwareRenderer {
+    type Texture = SoftwareTexture;
 
     fn create_texture(&mut self, width: u32, height: u32, data: &[Color]) -> Self::Texture {
         let mut pixels = vec![255 << 24; (width * height) as usize];
         for (i, pixel) in data.iter().enumerate() {
             pixels[i] = pixel.to_u32();
         }
-        CanvasTexture {
+        SoftwareTexture {
             width,
             height,
             pixels,
@@ -199,8 +196,9 @@ impl Renderer for CanvasRenderer {
             Some(src) => (src.x, src.y, src.width, src.height),
             None => (0, 0, texture.width, texture.height),
         };
-        for y in 0..src_height as i32 {
-            let sy = src_y + y;
+        // Nearest neighbour scaling from the source to the destination size
+        for y in 0..dst.height as i32 {
+            let sy = src_y + y * src_height as i32 / dst.height as i32;
             if sy < 0 || sy >= texture.height as i32 {
                 continue;
             }
@@ -210,8 +208,8 @@ impl Renderer for CanvasRenderer {
             }
             let si_start = (sy * texture.width as i32) as usize;
             let di_start = (dy * self.width as i32) as usize;
-            for x in 0..src_width as i32 {
-                let sx = src_x + x;
+            for x in 0..dst.width as i32 {
+                let sx = src_x + x * src_width as i32 / dst.width as i32;
                 if sx < 0 || sx >= texture.width as i32 {
                     continue;
                 }
@@ -235,6 +233,44 @@ impl Renderer for CanvasRenderer {
         }
     }
 
+    fn fill_rect_blended(&mut self, rect: &Rect, color: Color) {
+        let c = color.to_u32();
+        for y in rect.y..rect.y + rect.height as i32 {
+            if y < 0 || y >= self.height as i32 {
+                continue;
+            }
+            let di_start = (y * self.width as i32) as usize;
+            for x in rect.x..rect.x + rect.width as i32 {
+                if x < 0 || x >= self.width as i32 {
+                    continue;
+                }
+                let di = di_start + x as usize;
+                self.screen[di] = blend(self.screen[di], c);
+            }
+        }
+    }
+
+    fn render_texture_additive(&mut self, texture: &Self::Texture, dst: Rect, strength: u8) {
+        for y in 0..dst.height as i32 {
+            let dy = dst.y + y;
+            if dy < 0 || dy >= self.height as i32 {
+                continue;
+            }
+            let si_start =
+                (y * texture.height as i32 / dst.height as i32) as usize * texture.width as usize;
+            let di_start = (dy * self.width as i32) as usize;
+            for x in 0..dst.width as i32 {
+                let dx = dst.x + x;
+                if dx < 0 || dx >= self.width as i32 {
+                    continue;
+                }
+                let si = si_start + (x * texture.width as i32 / dst.width as i32) as usize;
+                let di = di_start + dx as usize;
+                self.screen[di] = add(self.screen[di], texture.pixels[si], strength);
+            }
+        }
+    }
+
     fn window_size(&self) -> (u32, u32) {
         (self.width, self.height)
     }
@@ -250,3 +286,11 @@ fn blend(dst: u32, src: u32) -> u32 {
     let b = (src.b as f32 * alpha + dst.b as f32 * inv_alpha) as u8;
     Color::from((r, g, b, 255)).to_u32()
 }
+
+fn add(dst: u32, src: u32, strength: u8) -> u32 {
+    let dst = Color::from_u32(dst);
+    let src = Color::from_u32(src);
+    let weight = src.a as u32 * strength as u32 / 255;
+    let add = |dst: u8, src: u8| std::cmp::min(dst as u32 + src as u32 * weight / 255, 255) as u8;
+    Color::from((add(dst.r, src.r), add(dst.g, src.g), add(dst.b, src.b), 255)).to_u32()
+}
@@ -0,0 +1,103 @@
+use crate::context::Context;
+use crate::font::Font;
+use crate::graphics::Graphics;
+use crate::inspector::get_object_description;
+use crate::level::{LevelObject, TILE_SIZE};
+use crate::load_level::LevelLister;
+use crate::render::{Rect, Renderer, RendererColor, Texture};
+use crate::util::{get_level_coordinates_from_screen_coordinates, get_tile_text};
+
+pub fn get_height<T>(font: &Font<T>) -> u32 {
+    2 * get_line_height(font) + font.px(2)
+}
+
+// Size of the level area visible above the status bar, in level pixels
+pub fn get_level_view_size<T>(graphics: &Graphics, font: &Font<T>) -> (u32, u32) {
+    (
+        graphics.resolution_x * 100 / graphics.zoom,
+        (graphics.resolution_y - get_height(font)) * 100 / graphics.zoom,
+    )
+}
+
+fn get_line_height<T>(font: &Font<T>) -> u32 {
+    font.line_height() + font.px(2)
+}
+
+// Two lines at the bottom of the editor: what is under the mouse, and the editor state
+pub fn render<L: LevelLister, R: Renderer>(
+    renderer: &mut R,
+    context: &Context<L, R::Texture>,
+    tool: &str,
+    hovered_object: Option<LevelObject>,
+) {
+    let font = &context.font;
+    let graphics = &context.graphics;
+    let height = get_height(font);
+    let y = graphics.resolution_y - height;
+    let rect = Rect::new(-1, y as i32, graphics.resolution_x + 2, height + 1);
+    renderer.fill_rect(&rect, RendererColor::Black);
+    renderer.draw_rect(&rect, RendererColor::White);
+    let x = font.px(4);
+    let line_y = y + font.px(2);
+    font.render_text(
+        renderer,
+        &get_cursor_text(context, hovered_object),
+        (x, line_y),
+    );
+    font.render_text(
+        renderer,
+        &get_editor_text(context, tool),
+        (x, line_y + get_line_height(font)),
+    );
+}
+
+fn get_cursor_text<L: LevelLister, T: Texture>(
+    context: &Context<L, T>,
+    hovered_object: Option<LevelObject>,
+) -> String {
+    let level = &context.level;
+    let (x, y) = get_level_coordinates_from_screen_coordinates(
+        &context.graphics,
+        &context.mouse,
+        &context.viewport,
+    );
+    let tile = match level
+        .tiles
+        .get((y / TILE_SIZE) as usize)
+        .and_then(|row| row.get((x / TILE_SIZE) as usize))
+    {
+        Some(tile) => tile,
+        None => return "outside level".to_string(),
+    };
+    let mut text = format!(
+        "tile {},{}  pixel {},{}  {}  shadow {}",
+        x / TILE_SIZE,
+        y / TILE_SIZE,
+        x,
+        y,
+        get_tile_text(tile.texture_type, tile.id),
+        tile.shadow
+    );
+    if let Some(object) = hovered_object {
+        text += &format!("  |  {}", get_object_description(level, &object));
+    }
+    text
+}
+
+fn get_editor_text<L: LevelLister, T: Texture>(context: &Context<L, T>, tool: &str) -> String {
+    let level = &context.level;
+    format!(
+        "{}  |  {}  |  level {}x{}  zoom {}%  |  {}{}",
+        tool,
+        get_tile_text(context.texture_type_selected, context.selected_tile_id),
+        level.tiles[0].len(),
+        level.tiles.len(),
+        context.graphics.zoom,
+        context.saved_level_name.as_deref().unwrap_or("not saved"),
+        if context.unsaved_changes {
+            " (unsaved changes)"
+        } else {
+            ""
+        }
+    )
+}
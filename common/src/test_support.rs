@@ -0,0 +1,191 @@
+// In-memory implementations of the frontend traits for driving State in tests without SDL or a
+// browser. Enabled with the test-support feature.
+
+use crate::context::{Context, Textures};
+use crate::editor::LevelWriter;
+use crate::fn2::{Character, FN2};
+use crate::font::Font;
+use crate::graphics::Graphics;
+use crate::level::{Level, TILE_SIZE};
+use crate::load_level::LevelLister;
+use crate::render::{create_crate_icons, create_light_texture, Color, Renderer};
+use crate::shadow_rules::ShadowRules;
+use crate::types::{TextureType, Trigonometry};
+use crate::TextInput;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::io;
+
+#[derive(Default)]
+pub struct MemoryLevelLister {
+    levels: Vec<(String, Vec<u8>)>,
+    sidecars: HashMap<(String, String), Vec<u8>>,
+    pub fail_loads: bool, // load_level returns no data, as if reading the file failed
+    pub refresh_count: u32,
+}
+
+impl MemoryLevelLister {
+    pub fn add_level(&mut self, name: &str, data: Vec<u8>) {
+        self.levels.push((name.to_string(), data));
+    }
+
+    pub fn add_sidecar(&mut self, level_name: &str, extension: &str, data: Vec<u8>) {
+        self.sidecars
+            .insert((level_name.to_string(), extension.to_string()), data);
+    }
+}
+
+impl LevelLister for MemoryLevelLister {
+    fn refresh(&mut self) {
+        self.refresh_count += 1;
+    }
+
+    fn reset(&mut self) {}
+
+    fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    fn level_name(&self, index: usize) -> &str {
+        &self.levels[index].0
+    }
+
+    fn load_level(&self, index: usize) -> Vec<u8> {
+        if self.fail_loads {
+            return Vec::new();
+        }
+        self.levels[index].1.clone()
+    }
+
+    fn load_sidecar(&self, index: usize, extension: &str) -> Option<Vec<u8>> {
+        self.sidecars
+            .get(&(self.levels[index].0.clone(), extension.to_string()))
+            .cloned()
+    }
+}
+
+thread_local! {
+    static WRITES: RefCell<Vec<(String, Vec<u8>)>> = const { RefCell::new(Vec::new()) };
+    static REMOVALS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    static FAIL_WRITES: Cell<bool> = const { Cell::new(false) };
+}
+
+// LevelWriter has no instance, so the writes are recorded per thread. Tests run in their own
+// threads and do not see each other's writes.
+pub struct MemoryLevelWriter;
+
+impl MemoryLevelWriter {
+    // Files written since the previous call, in write order
+    pub fn take_writes() -> Vec<(String, Vec<u8>)> {
+        WRITES.with(|writes| writes.take())
+    }
+
+    // Files removed since the previous call, in removal order
+    pub fn take_removals() -> Vec<String> {
+        REMOVALS.with(|removals| removals.take())
+    }
+
+    pub fn set_failing(fail: bool) {
+        FAIL_WRITES.with(|fail_writes| fail_writes.set(fail));
+    }
+}
+
+impl LevelWriter for MemoryLevelWriter {
+    fn write(filename: &str, level_data: &[u8]) -> io::Result<()> {
+        if FAIL_WRITES.with(Cell::get) {
+            return Err(io::Error::other("simulated write failure"));
+        }
+        WRITES.with(|writes| {
+            writes
+                .borrow_mut()
+                .push((filename.to_string(), level_data.to_vec()))
+        });
+        Ok(())
+    }
+
+    fn remove(filename: &str) -> io::Result<()> {
+        if FAIL_WRITES.with(Cell::get) {
+            return Err(io::Error::other("simulated write failure"));
+        }
+        REMOVALS.with(|removals| removals.borrow_mut().push(filename.to_string()));
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TextInputCall {
+    Start,
+    Stop,
+}
+
+#[derive(Default)]
+pub struct RecordingTextInput {
+    pub calls: Vec<TextInputCall>,
+}
+
+impl RecordingTextInput {
+    pub fn is_active(&self) -> bool {
+        self.calls.last() == Some(&TextInputCall::Start)
+    }
+}
+
+impl TextInput for RecordingTextInput {
+    fn start(&mut self) {
+        self.calls.push(TextInputCall::Start);
+    }
+
+    fn stop(&mut self) {
+        self.calls.push(TextInputCall::Stop);
+    }
+}
+
+// Context with a blank font and blank tile textures of the original sizes, for tests which do not
+// look at the rendered image
+pub fn create_test_context<R: Renderer>(
+    renderer: &mut R,
+    level: Level,
+) -> Context<MemoryLevelLister, R::Texture> {
+    let fn2 = FN2 {
+        first_visible_character: 33,
+        characters: (0..92)
+            .map(|_| Character {
+                width: 6,
+                height: 7,
+                lines: Vec::new(),
+            })
+            .collect(),
+    };
+    let tiles = |renderer: &mut R, (width, height): (u32, u32)| {
+        renderer.create_texture(
+            width * TILE_SIZE,
+            height * TILE_SIZE,
+            &vec![Color::from((0, 0, 0)); (width * height * TILE_SIZE * TILE_SIZE) as usize],
+        )
+    };
+    let textures = Textures {
+        floor: tiles(renderer, (16, 10)),
+        walls: tiles(renderer, (16, 10)),
+        shadows: tiles(renderer, (6, 1)),
+        light: create_light_texture(renderer),
+        crates: create_crate_icons(renderer, &fn2),
+    };
+    Context {
+        graphics: Graphics::new(renderer.window_size(), 1),
+        font: Font::new(renderer, &fn2, 1),
+        fn2,
+        textures,
+        level,
+        level_lister: MemoryLevelLister::default(),
+        selected_tile_id: 0,
+        texture_type_selected: TextureType::Floor,
+        texture_type_scrolled: TextureType::Floor,
+        mouse: (0, 0),
+        viewport: (0, 0),
+        level_save_name: String::new(),
+        saved_level_name: None,
+        unsaved_changes: false,
+        trigonometry: Trigonometry::new(),
+        automatic_shadows: true,
+        shadow_rules: ShadowRules::default(),
+    }
+}
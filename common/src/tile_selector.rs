@@ -1,5 +1,5 @@
 use crate::context::Context;
-use crate::event::{Event, Keycode, MouseButton};
+use crate::event::{wheel_to_key_down, Event, Keycode, MouseButton};
 use crate::load_level::LevelLister;
 use crate::render::{
     get_texture_rect, get_texture_render_size, highlight_selected_tile, Renderer, RendererColor,
@@ -25,11 +25,15 @@ impl TileSelectState {
             Event::Quit
             | Event::KeyDown {
                 keycode: Keycode::Escape,
+                ..
             } => return EventResult::ChangeMode(Mode::Editor),
             Event::Window { .. } => {
                 return EventResult::ChangeMode(Mode::Editor);
             }
-            Event::KeyDown { keycode } => match keycode {
+            Event::MouseWheel { y, .. } if y != 0 => {
+                return self.handle_event(context, wheel_to_key_down(y));
+            }
+            Event::KeyDown { keycode, .. } => match keycode {
                 Keycode::Space => {
                     return EventResult::ChangeMode(Mode::Editor);
                 }
@@ -63,17 +67,18 @@ impl TileSelectState {
                 button: MouseButton::Left,
                 ..
             } => {
+                let graphics = context.graphics.unzoomed();
                 let texture_selected = match &context.texture_type_scrolled {
                     TextureType::Floor => &context.textures.floor,
                     TextureType::Walls => &context.textures.walls,
                     TextureType::Shadow => &context.textures.shadows,
                 };
                 let (texture_width, texture_height) =
-                    get_texture_render_size(texture_selected, context.graphics.render_multiplier);
+                    get_texture_render_size(texture_selected, graphics.render_multiplier);
                 let clicked_tile_id = get_tile_id_from_coordinates(
-                    &context.graphics,
+                    &graphics,
                     &limit_coordinates(&context.mouse, &(texture_width, texture_height)),
-                    texture_width / context.graphics.get_render_size(),
+                    texture_width / graphics.get_render_size(),
                     None,
                 );
                 if clicked_tile_id < get_number_of_tiles_in_texture(texture_selected) {
@@ -97,45 +102,36 @@ impl TileSelectState {
             TextureType::Walls => &context.textures.walls,
             TextureType::Shadow => &context.textures.shadows,
         };
-        let render_multiplier = context.graphics.render_multiplier;
+        let graphics = context.graphics.unzoomed();
+        let render_multiplier = graphics.render_multiplier;
         let dst = get_texture_rect(texture_selected, render_multiplier);
         renderer.fill_rect(&dst, RendererColor::LightGrey);
         renderer.render_texture(&texture_selected, None, dst);
         let (texture_width, texture_height) =
             get_texture_render_size(texture_selected, render_multiplier);
         let highlighted_id = get_tile_id_from_coordinates(
-            &context.graphics,
+            &graphics,
             &limit_coordinates(&context.mouse, &(texture_width, texture_height)),
-            context.graphics.get_x_tiles_per_screen(),
+            graphics.get_x_tiles_per_screen(),
             None,
         );
-        highlight_selected_tile(
-            renderer,
-            &context.graphics,
-            highlighted_id,
-            RendererColor::White,
-        );
+        highlight_selected_tile(renderer, &graphics, highlighted_id, RendererColor::White);
         if context.texture_type_selected == context.texture_type_scrolled {
             let coordinates = get_tile_coordinates(
                 context.selected_tile_id,
-                texture_width / context.graphics.render_multiplier,
+                texture_width / graphics.render_multiplier,
             );
-            let render_multiplier = context.graphics.render_multiplier;
+            let render_multiplier = graphics.render_multiplier;
             let screen_tile_id = get_tile_id_from_coordinates(
-                &context.graphics,
+                &graphics,
                 &(
                     coordinates.0 * render_multiplier,
                     coordinates.1 * render_multiplier,
                 ),
-                context.graphics.get_x_tiles_per_screen(),
+                graphics.get_x_tiles_per_screen(),
                 None,
             );
-            highlight_selected_tile(
-                renderer,
-                &context.graphics,
-                screen_tile_id,
-                RendererColor::Red,
-            );
+            highlight_selected_tile(renderer, &graphics, screen_tile_id, RendererColor::Red);
         }
         let active_text = match context.texture_type_scrolled {
             TextureType::Floor => "floor blocks (PAGEGUP/DOWN)",
@@ -145,7 +141,7 @@ impl TileSelectState {
         context.font.render_text(
             renderer,
             active_text,
-            get_bottom_text_position(&context.font, context.graphics.resolution_y),
+            get_bottom_text_position(&context.font, graphics.resolution_y),
         );
     }
 }
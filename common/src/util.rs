@@ -4,7 +4,7 @@ use std::cmp;
 use crate::graphics::Graphics;
 use crate::level::TILE_SIZE;
 use crate::render::{Point, Texture};
-use crate::types::Trigonometry;
+use crate::types::{TextureType, Trigonometry};
 
 pub fn get_title_position<T>(font: &Font<T>) -> (u32, u32) {
     (font.px(10), font.px(5))
@@ -21,71 +21,67 @@ pub fn get_tile_coordinates(id: u32, width: u32) -> (u32, u32) {
     (x, y)
 }
 
+// Tile coordinates under screen coordinates, viewport being the level pixel shown at the top
+// left corner of the screen
 pub fn get_logical_coordinates(
     graphics: &Graphics,
     x: u32,
     y: u32,
-    scroll: Option<(u32, u32)>,
+    viewport: Option<(u32, u32)>,
 ) -> (u32, u32) {
-    let render_multiplier = graphics.render_multiplier;
-    let scroll = scroll.unwrap_or((0, 0));
-    (
-        x / render_multiplier / TILE_SIZE + scroll.0,
-        y / render_multiplier / TILE_SIZE + scroll.1,
-    )
+    let (x, y) = get_level_coordinates_from_screen_coordinates(
+        graphics,
+        &(x, y),
+        &viewport.unwrap_or((0, 0)),
+    );
+    (x / TILE_SIZE, y / TILE_SIZE)
 }
 
 pub fn get_tile_id_from_coordinates(
     graphics: &Graphics,
     coordinates: &(u32, u32),
     x_blocks: u32,
-    scroll: Option<(u32, u32)>,
+    viewport: Option<(u32, u32)>,
 ) -> u32 {
     let (x_logical, y_logical) =
-        get_logical_coordinates(graphics, coordinates.0, coordinates.1, scroll);
+        get_logical_coordinates(graphics, coordinates.0, coordinates.1, viewport);
     x_logical + y_logical * x_blocks
 }
 
-pub fn get_scroll_corrected_indexes(
-    scroll: (u32, u32),
-    x_index: u32,
-    y_index: u32,
-) -> (usize, usize) {
-    ((x_index + scroll.0) as usize, (y_index + scroll.1) as usize)
-}
-
-pub fn get_absolute_coordinates_from_logical(x: u32, y: u32, render_size: u32) -> (i32, i32) {
-    (
-        (x * render_size).try_into().unwrap(),
-        (y * render_size).try_into().unwrap(),
-    )
-}
-
 pub fn get_level_coordinates_from_screen_coordinates(
     graphics: &Graphics,
     coordinates: &(u32, u32),
-    scroll: &(u32, u32),
+    viewport: &(u32, u32),
 ) -> (u32, u32) {
-    let render_multiplier = graphics.render_multiplier;
+    let zoom = graphics.zoom;
     (
-        coordinates.0 / render_multiplier + scroll.0 * TILE_SIZE,
-        coordinates.1 / render_multiplier + scroll.1 * TILE_SIZE,
+        coordinates.0 * 100 / zoom + viewport.0,
+        coordinates.1 * 100 / zoom + viewport.1,
     )
 }
 
 pub fn get_screen_coordinates_from_level_coordinates(
     graphics: &Graphics,
     coordinates: &(u32, u32),
-    scroll: &(u32, u32),
+    viewport: &(u32, u32),
 ) -> Point {
-    let render_multiplier = graphics.render_multiplier;
-    let render_size = graphics.get_render_size();
+    let zoom = graphics.zoom as i32;
+    // Rounding down also left of the screen keeps the tiles adjacent
     Point::new(
-        (coordinates.0 * render_multiplier) as i32 - (scroll.0 * render_size) as i32,
-        (coordinates.1 * render_multiplier) as i32 - (scroll.1 * render_size) as i32,
+        ((coordinates.0 as i32 - viewport.0 as i32) * zoom).div_euclid(100),
+        ((coordinates.1 as i32 - viewport.1 as i32) * zoom).div_euclid(100),
     )
 }
 
+pub fn get_tile_text(texture_type: TextureType, id: u32) -> String {
+    let texture_text = match texture_type {
+        TextureType::Floor => "floor",
+        TextureType::Walls => "wall",
+        TextureType::Shadow => "shadow",
+    };
+    format!("{} {}", texture_text, id)
+}
+
 pub fn get_distance_between_points(p0: &(u32, u32), p1: &(u32, u32)) -> f64 {
     let x0 = p0.0 as i32;
     let x1 = p1.0 as i32;
@@ -98,6 +94,11 @@ pub fn get_spotlight_render_radius(spotlight: &u8) -> u32 {
     *spotlight as u32 * 5 + 5
 }
 
+// Approximate reach of the spotlight in the game, in level pixels
+pub fn get_spotlight_light_radius(spotlight: &u8) -> u32 {
+    *spotlight as u32 * 12 + 24
+}
+
 pub fn get_steam_render_radius() -> u32 {
     5
 }
@@ -122,20 +123,20 @@ pub fn get_selected_level_tiles(
     p0: &(u32, u32),
     p1: &(u32, u32),
     x_blocks: u32,
-    scroll: Option<(u32, u32)>,
+    viewport: Option<(u32, u32)>,
 ) -> Vec<u32> {
     let tile_ids = (
         get_tile_id_from_coordinates(
             graphics,
             &(cmp::min(p0.0, p1.0), cmp::min(p0.1, p1.1)),
             x_blocks,
-            scroll,
+            viewport,
         ),
         get_tile_id_from_coordinates(
             graphics,
             &(cmp::max(p0.0, p1.0), cmp::max(p0.1, p1.1)),
             x_blocks,
-            scroll,
+            viewport,
         ),
     );
     let x_diff = (tile_ids.1 - tile_ids.0) % x_blocks + 1;
@@ -0,0 +1,251 @@
+use common::level::{
+    CrateClass, Level, LevelObject, StaticCrate, StaticCrateType, Steam, TileMatch,
+};
+use common::level_dump::{dump_level, get_problems};
+use common::level_text::{format_level, parse_level};
+use common::types::TextureType;
+
+fn get_test_level() -> Level {
+    let mut level = Level::get_default_level((12, 8));
+    level.put_tile_to_level(13, Some(4), &TextureType::Walls);
+    level.put_tile_to_level(14, Some(2), &TextureType::Shadow);
+    level.put_spotlight_to_level(&(60, 40), 7);
+    level.put_steam_to_level(
+        &(100, 80),
+        &Steam {
+            range: 2,
+            angle: 45,
+        },
+    );
+    for (x, crate_variant, crate_class, crate_type) in [
+        (40, StaticCrate::Normal, CrateClass::Weapon, 3),
+        (80, StaticCrate::Deathmatch, CrateClass::Bullet, 8),
+        (120, StaticCrate::Deathmatch, CrateClass::Energy, 0),
+    ] {
+        level.put_crate_to_level(
+            &(x, 100),
+            &StaticCrateType {
+                crate_variant,
+                crate_class,
+                crate_type,
+            },
+        );
+    }
+    level.general_info.comment = "two  spaces".to_string();
+    level
+}
+
+#[test]
+fn find_object_picks_the_nearest_of_overlapping_objects() {
+    // Every level has new HashMap orders, which must not change the picks
+    for _ in 0..20 {
+        let mut level = Level::get_default_level((12, 8));
+        for position in [(100, 100), (112, 100), (150, 100), (80, 80)] {
+            level.put_spotlight_to_level(&position, 5);
+        }
+        level.put_steam_to_level(&(150, 100), &Steam { range: 1, angle: 0 });
+        level.put_crate_to_level(
+            &(60, 60),
+            &StaticCrateType {
+                crate_variant: StaticCrate::Normal,
+                crate_class: CrateClass::Weapon,
+                crate_type: 0,
+            },
+        );
+
+        let find = |position| level.find_object(&position, 100);
+        assert!(find((110, 100)) == Some(LevelObject::Spotlight((112, 100))));
+        assert!(find((106, 100)) == Some(LevelObject::Spotlight((100, 100))));
+        assert!(find((151, 100)) == Some(LevelObject::Steam((150, 100))));
+        assert!(find((78, 78)) == Some(LevelObject::Spotlight((80, 80))));
+        assert!(find((62, 62)) == Some(LevelObject::Crate((60, 60))));
+    }
+}
+
+#[test]
+fn serialized_level_loads_both_crate_variants() {
+    let level = Level::deserialize(&get_test_level().serialize()).unwrap();
+    let deathmatch_crates = level
+        .crates
+        .staticc
+        .values()
+        .filter(|crate_item| crate_item.crate_variant == StaticCrate::Deathmatch)
+        .count();
+    assert_eq!(level.crates.staticc.len(), 3);
+    assert_eq!(deathmatch_crates, 2);
+}
+
+#[test]
+fn shadow_locks_serialize_in_position_order() {
+    let mut level = Level::get_default_level((12, 8));
+    for position in [(5, 3), (1, 7), (9, 3), (2, 0)] {
+        level.shadow_locks.insert(position);
+    }
+    let data = level.serialize_shadow_locks();
+    let positions = data[8..]
+        .chunks(8)
+        .map(|chunk| {
+            (
+                u32::from_le_bytes(chunk[..4].try_into().unwrap()),
+                u32::from_le_bytes(chunk[4..].try_into().unwrap()),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(positions, [(2, 0), (5, 3), (9, 3), (1, 7)]);
+}
+
+#[test]
+fn text_format_round_trips() {
+    let level = get_test_level();
+    let text = format_level(&level);
+    let parsed = parse_level(&text).unwrap();
+    assert_eq!(format_level(&parsed), text);
+    assert!(parsed.serialize().len() == level.serialize().len());
+    assert_eq!(parsed.general_info.comment, "two  spaces");
+    assert!(parsed.shadow_locks.contains(&(2, 1)));
+}
+
+#[test]
+fn text_format_errors_have_line_numbers() {
+    let error = parse_level("size 2 1\ntiles f0 x1\n").err().unwrap();
+    assert_eq!(error.line, 2);
+    assert_eq!(error.message, "invalid tile x1");
+
+    let error = parse_level("size 2 1\ntiles f0 s1\n").err().unwrap();
+    assert_eq!(error.message, "invalid tile s1");
+
+    let error = parse_level("size 2 2\ntiles f0 f0\n").err().unwrap();
+    assert_eq!(error.message, "expected 2 rows of tiles, got 1");
+}
+
+#[test]
+fn dump_covers_every_byte_of_a_saved_level() {
+    let data = get_test_level().serialize();
+    let sections = dump_level(&data);
+    assert_eq!(get_problems(&sections).count(), 0);
+
+    let mut offset = 0;
+    for field in sections.iter().flat_map(|section| &section.fields) {
+        assert_eq!(field.offset, offset, "{}", field.name);
+        assert_eq!(field.bytes, data[offset..offset + field.bytes.len()]);
+        offset += field.bytes.len();
+    }
+    assert_eq!(offset, data.len());
+}
+
+#[test]
+fn dump_flags_truncated_and_trailing_data() {
+    let mut data = get_test_level().serialize();
+    let length = data.len();
+    data.extend_from_slice(&[1, 2, 3]);
+    let sections = dump_level(&data);
+    let problems = get_problems(&sections).collect::<Vec<_>>();
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].offset, length);
+    assert_eq!(problems[0].name, "unread bytes");
+
+    let sections = dump_level(&data[..length - 2]);
+    let problems = get_problems(&sections).collect::<Vec<_>>();
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].offset, length - 4);
+    assert_eq!(
+        problems[0].problem.as_deref(),
+        Some("data ends here, 4 bytes needed but 2 left")
+    );
+    assert!(Level::deserialize(&data[..length - 2]).is_err());
+}
+
+#[test]
+fn dump_flags_values_which_load_differently() {
+    let mut level = get_test_level();
+    level.put_spotlight_to_level(&(100, 40), 3);
+    let mut data = level.serialize();
+    // Header, tiles, player starts and spotlight amount come before the first spotlight
+    let first_spotlight = 12 + 12 * 12 * 8 + 16 + 4;
+    data[first_spotlight + 8..first_spotlight + 12].copy_from_slice(&300u32.to_le_bytes());
+
+    let sections = dump_level(&data);
+    let problems = get_problems(&sections).collect::<Vec<_>>();
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].name, "spotlight 0 intensity");
+    assert_eq!(
+        problems[0].problem.as_deref(),
+        Some("does not fit in 8 bits and loads as 44")
+    );
+}
+
+// 10x8 level with wall 7 on the diagonal from 1,1 to 6,6, shadow 3 on the even positions
+fn get_diagonal_level() -> Level {
+    let mut level = Level::get_default_level((10, 8));
+    for i in 1..7 {
+        level.put_tile_to_level(i * 10 + i, Some(7), &TextureType::Walls);
+        let shadow = if i % 2 == 0 { Some(2) } else { None };
+        level.put_tile_to_level(i * 10 + i, shadow, &TextureType::Shadow);
+    }
+    level
+}
+
+fn wall(id: u32, shadow: Option<u32>) -> TileMatch {
+    TileMatch {
+        texture_type: TextureType::Walls,
+        id,
+        shadow,
+    }
+}
+
+#[test]
+fn find_tiles_limits_to_inclusive_area() {
+    let level = get_diagonal_level();
+    assert_eq!(level.find_tiles(&wall(7, None), None).len(), 6);
+    assert_eq!(
+        level.find_tiles(&wall(7, None), Some(((2, 2), (4, 5)))),
+        [(2, 2), (3, 3), (4, 4)]
+    );
+    // Area reaching outside the level is cut to the level
+    assert_eq!(
+        level.find_tiles(&wall(7, None), Some(((5, 5), (100, 100)))),
+        [(5, 5), (6, 6)]
+    );
+    assert!(level
+        .find_tiles(&wall(7, None), Some(((7, 0), (9, 7))))
+        .is_empty());
+}
+
+#[test]
+fn find_tiles_matches_shadow_only_when_given() {
+    let level = get_diagonal_level();
+    assert_eq!(
+        level.find_tiles(&wall(7, Some(3)), None),
+        [(2, 2), (4, 4), (6, 6)]
+    );
+    assert_eq!(
+        level.find_tiles(&wall(7, Some(0)), None),
+        [(1, 1), (3, 3), (5, 5)]
+    );
+    assert!(level.find_tiles(&wall(8, None), None).is_empty());
+}
+
+#[test]
+fn replace_tiles_keeps_shadows_and_other_tiles() {
+    let mut level = get_diagonal_level();
+    let count = level.replace_tiles(
+        &wall(7, Some(3)),
+        (TextureType::Floor, 5),
+        Some(((0, 0), (4, 4))),
+    );
+    assert_eq!(count, 2);
+    for (position, expected) in [
+        ((2, 2), (TextureType::Floor, 5, 3)),
+        ((4, 4), (TextureType::Floor, 5, 3)),
+        ((3, 3), (TextureType::Walls, 7, 0)),
+        ((6, 6), (TextureType::Walls, 7, 3)),
+    ] {
+        let tile = level.get_tile_match(&position).unwrap();
+        assert!(tile.texture_type == expected.0, "{:?}", position);
+        assert_eq!((tile.id, tile.shadow), (expected.1, Some(expected.2)));
+    }
+    assert_eq!(
+        level.replace_tiles(&wall(7, Some(3)), (TextureType::Floor, 5), None),
+        1
+    );
+}
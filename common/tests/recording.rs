@@ -0,0 +1,82 @@
+use common::event::{Event, Keycode, Modifiers, MouseButton, WindowEvent};
+use common::recording::{format_event, format_header, format_line, Recording};
+
+#[test]
+fn formatted_events_parse_back() {
+    let events = [
+        Event::Quit,
+        Event::Window {
+            win_event: WindowEvent::Resized {
+                width: 1280,
+                height: 800,
+            },
+        },
+        Event::KeyDown {
+            keycode: Keycode::KpPlus,
+            modifiers: Modifiers {
+                shift: true,
+                ctrl: true,
+                alt: false,
+            },
+        },
+        Event::MouseButtonDown {
+            button: MouseButton::Right,
+            modifiers: Modifiers::default(),
+        },
+        Event::MouseWheel {
+            x: 0,
+            y: -2,
+            modifiers: Modifiers {
+                alt: true,
+                ..Modifiers::default()
+            },
+        },
+        Event::TextInput {
+            text: "two  spaces".to_string(),
+        },
+        Event::FileUpload {
+            name: "my level.lev".to_string(),
+            data: vec![0, 1, 0xab, 0xff],
+        },
+        Event::FileUpload {
+            name: " EMPTY.LEV".to_string(),
+            data: Vec::new(),
+        },
+        Event::Tick { milliseconds: 100 },
+    ];
+    let mut text = format_header((1280, 800), 2);
+    for (milliseconds, event) in events.iter().enumerate() {
+        text.push_str(&format_line(milliseconds as u64 * 10, event));
+    }
+
+    let recording = Recording::parse(&text).unwrap();
+    assert_eq!(recording.screen, (1280, 800));
+    assert_eq!(recording.render_multiplier, 2);
+    assert_eq!(recording.events.len(), events.len());
+    assert!(matches!(
+        &recording.events[5].1,
+        Event::TextInput { text } if text == "two  spaces"
+    ));
+    assert!(matches!(
+        &recording.events[6].1,
+        Event::FileUpload { name, data } if name == "my level.lev" && data.len() == 4
+    ));
+    assert!(matches!(
+        &recording.events[7].1,
+        Event::FileUpload { name, data } if name == " EMPTY.LEV" && data.is_empty()
+    ));
+    for (index, ((milliseconds, parsed), event)) in recording.events.iter().zip(&events).enumerate()
+    {
+        assert_eq!(*milliseconds, index as u64 * 10);
+        assert_eq!(format_event(parsed), format_event(event));
+    }
+}
+
+#[test]
+fn invalid_line_is_reported() {
+    let error = Recording::parse("screen 640 400 1\n0 key_down Space\n5 key_down Nope\n")
+        .err()
+        .unwrap();
+    assert_eq!(error.line, 3);
+    assert_eq!(error.message, "invalid key Nope");
+}
@@ -0,0 +1,116 @@
+use common::level::Level;
+use common::shadow_rules::{ShadowRules, ShadowRulesError};
+use common::types::TextureType;
+
+// Small xorshift generator to get reproducible "random" edits without extra dependencies
+struct Random(u32);
+
+impl Random {
+    fn next(&mut self, max: u32) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 % max
+    }
+}
+
+#[test]
+fn incremental_shadows_match_full_recomputation() {
+    let size = (40u8, 30u8);
+    let width = size.0 as u32;
+    let height = size.1 as u32;
+    let mut incremental = Level::get_default_level(size);
+    let mut full = Level::get_default_level(size);
+    let mut random = Random(0x1234_5678);
+    let rules = ShadowRules::default();
+
+    for _ in 0..500 {
+        let x0 = random.next(width);
+        let y0 = random.next(height);
+        let x1 = std::cmp::min(x0 + random.next(4), width - 1);
+        let y1 = std::cmp::min(y0 + random.next(4), height - 1);
+        let texture_type = if random.next(2) == 0 {
+            TextureType::Floor
+        } else {
+            TextureType::Walls
+        };
+        let id = random.next(16);
+        let manual_shadow = random.next(10) == 0;
+
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                for level in [&mut incremental, &mut full] {
+                    if manual_shadow {
+                        level.put_tile_to_level(x + y * width, Some(id % 4), &TextureType::Shadow);
+                    } else {
+                        level.put_tile_to_level(x + y * width, Some(id), &texture_type);
+                    }
+                }
+            }
+        }
+        if !manual_shadow {
+            incremental.create_shadows_for_area(((x0, y0), (x1, y1)), &rules);
+            full.create_shadows(&rules);
+        }
+
+        assert!(incremental.serialize() == full.serialize());
+    }
+}
+
+#[test]
+fn parsed_rules_override_defaults() {
+    let rules = ShadowRules::parse(
+        "# only the first walls cast shadows\n\
+         casters 0-3 10\n\
+         \n\
+         shadow 100 6 # top only\n",
+    )
+    .unwrap();
+    assert!(rules.casts_shadow(0));
+    assert!(rules.casts_shadow(3));
+    assert!(!rules.casts_shadow(4));
+    assert!(rules.casts_shadow(10));
+    assert_eq!(rules.get_shadow(true, false, false), 6);
+    assert_eq!(
+        rules.get_shadow(false, false, true),
+        ShadowRules::default().get_shadow(false, false, true)
+    );
+
+    let rules = ShadowRules::parse("casters 5\ncasters all\n").unwrap();
+    assert!(rules.casts_shadow(100));
+}
+
+#[test]
+fn invalid_rules_report_their_line() {
+    for (text, line) in [
+        ("shade 100 1", 1),
+        ("casters 0-15\ncasters 7-3", 2),
+        ("casters x", 1),
+        ("shadow 100", 1),
+        ("shadow 102 1", 1),
+        ("shadow 1000 1", 1),
+        ("shadow 100 1 2", 1),
+        ("\n\nshadow 100 -1", 3),
+    ] {
+        assert!(
+            matches!(ShadowRules::parse(text), Err(ShadowRulesError::InvalidLine(l)) if l == line),
+            "{}",
+            text
+        );
+    }
+}
+
+#[test]
+fn shadows_outside_shadows_alpha_are_rejected() {
+    let parse = |text| ShadowRules::parse(text).unwrap();
+    assert!(parse("shadow 111 0").check_shadows(6).is_ok());
+    assert!(parse("shadow 111 6").check_shadows(6).is_ok());
+    assert!(matches!(
+        parse("# comment\nshadow 111 7").check_shadows(6),
+        Err(ShadowRulesError::InvalidShadow(7))
+    ));
+    // Larger shadow tilesets of mods
+    assert!(parse("shadow 111 12").check_shadows(12).is_ok());
+    assert!(ShadowRules::default().check_shadows(3).is_ok());
+    assert!(ShadowRules::default().check_shadows(2).is_err());
+}
@@ -0,0 +1,317 @@
+// Renders editor screens with the software renderer and compares them to the PNG images in
+// tests/snapshots. Run with UPDATE_SNAPSHOTS=1 to rewrite the images after intended changes.
+
+use common::context::{Context, Textures};
+use common::event::{Event, Keycode, Modifiers, MouseButton};
+use common::fn2::FN2;
+use common::font::Font;
+use common::level::{CrateClass, Level, StaticCrate, StaticCrateType, Steam};
+use common::recording::Recording;
+use common::render::{create_crate_icons, create_light_texture, Color};
+use common::shadow_rules::ShadowRules;
+use common::software_renderer::{SoftwareRenderer, SoftwareTexture};
+use common::test_support::{
+    create_test_context, MemoryLevelLister, MemoryLevelWriter, RecordingTextInput,
+};
+use common::types::TextureType;
+use common::State;
+use png::{BitDepth, ColorType, Decoder, Encoder, Transformations};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+const SCREEN_SIZE: (u32, u32) = (640, 400);
+
+struct Harness {
+    state: State<MemoryLevelWriter>,
+    context: Context<MemoryLevelLister, SoftwareTexture>,
+    renderer: SoftwareRenderer,
+    text_input: RecordingTextInput,
+}
+
+impl Harness {
+    fn new() -> Self {
+        let mut renderer = SoftwareRenderer::new(SCREEN_SIZE.0, SCREEN_SIZE.1);
+        let mut level = get_test_level();
+        level.create_shadows(&ShadowRules::default());
+        let mut context = create_test_context(&mut renderer, level);
+        context.fn2 = FN2::parse(&fs::read(asset_path("TETRIS.FN2")).unwrap());
+        context.font = Font::new(&mut renderer, &context.fn2, 1);
+        context.textures = Textures {
+            floor: load_texture(&renderer, &asset_path("FLOOR1.PNG")),
+            walls: load_texture(&renderer, &asset_path("WALLS1.PNG")),
+            shadows: load_texture(&renderer, &asset_path("SHADOWS_ALPHA.PNG")),
+            light: create_light_texture(&mut renderer),
+            crates: create_crate_icons(&mut renderer, &context.fn2),
+        };
+        context
+            .level_lister
+            .add_level("FIRST.LEV", get_test_level().serialize());
+        context
+            .level_lister
+            .add_level("SECOND.LEV", get_test_level().serialize());
+        Harness {
+            state: State::new(),
+            context,
+            renderer,
+            text_input: RecordingTextInput::default(),
+        }
+    }
+
+    fn send(&mut self, event: Event) {
+        self.state
+            .handle_event(&mut self.context, &mut self.text_input, event);
+    }
+
+    // Sends the events of a recording in tests/recordings, which must have the harness screen size
+    fn replay(&mut self, name: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/recordings")
+            .join(name);
+        let recording = Recording::parse(&fs::read_to_string(&path).unwrap())
+            .unwrap_or_else(|e| panic!("{}:{}: {}", path.display(), e.line, e.message));
+        assert_eq!(recording.screen, SCREEN_SIZE);
+        for (_, event) in recording.events {
+            self.send(event);
+        }
+    }
+
+    fn key(&mut self, keycode: Keycode) {
+        self.send(Event::KeyDown {
+            keycode,
+            modifiers: Modifiers::default(),
+        });
+    }
+
+    fn click(&mut self, x: u32, y: u32) {
+        self.send(Event::MouseMotion { x, y });
+        self.send(Event::MouseButtonDown {
+            button: MouseButton::Left,
+            modifiers: Modifiers::default(),
+        });
+        self.send(Event::MouseButtonUp {
+            button: MouseButton::Left,
+        });
+    }
+
+    fn assert_snapshot(&mut self, name: &str) {
+        self.state.render(&mut self.renderer, &self.context);
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/snapshots")
+            .join(format!("{}.png", name));
+        let actual = self
+            .renderer
+            .pixels()
+            .iter()
+            .flat_map(|pixel| {
+                let color = Color::from_u32(*pixel);
+                [color.r, color.g, color.b]
+            })
+            .collect::<Vec<_>>();
+
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            write_png(&path, &actual);
+            return;
+        }
+        let expected = read_png(&path);
+        if expected != actual {
+            let actual_path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{}.png", name));
+            write_png(&actual_path, &actual);
+            let differing = expected
+                .chunks(3)
+                .zip(actual.chunks(3))
+                .filter(|(e, a)| e != a)
+                .count();
+            panic!(
+                "{} differs from the snapshot in {} pixels, rendered image written to {}. \
+                 Run with UPDATE_SNAPSHOTS=1 if the change is intended.",
+                name,
+                differing,
+                actual_path.display()
+            );
+        }
+    }
+}
+
+fn get_test_level() -> Level {
+    let mut level = Level::get_default_level((32, 22));
+    for x in 8..14 {
+        level.put_tile_to_level(x + 6 * 32, Some(1), &TextureType::Walls);
+    }
+    level.put_spotlight_to_level(&(100, 80), 4);
+    level.put_steam_to_level(
+        &(300, 200),
+        &Steam {
+            range: 3,
+            angle: 90,
+        },
+    );
+    for (x, crate_variant, crate_class) in [
+        (200, StaticCrate::Normal, CrateClass::Weapon),
+        (240, StaticCrate::Deathmatch, CrateClass::Bullet),
+        (280, StaticCrate::Normal, CrateClass::Energy),
+    ] {
+        level.put_crate_to_level(
+            &(x, 260),
+            &StaticCrateType {
+                crate_variant,
+                crate_class,
+                crate_type: 0,
+            },
+        );
+    }
+    level
+}
+
+fn asset_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../assets")
+        .join(name)
+}
+
+fn load_texture(renderer: &SoftwareRenderer, path: &Path) -> SoftwareTexture {
+    let mut decoder = Decoder::new(File::open(path).unwrap());
+    decoder.set_transformations(Transformations::normalize_to_color8() | Transformations::ALPHA);
+    let mut reader = decoder.read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).unwrap();
+    let data = &data[..info.buffer_size()];
+    let rgba = match info.color_type {
+        ColorType::Rgba => data.to_vec(),
+        ColorType::GrayscaleAlpha => data
+            .chunks(2)
+            .flat_map(|c| [c[0], c[0], c[0], c[1]])
+            .collect(),
+        color_type => panic!(
+            "{}: unsupported color type {:?}",
+            path.display(),
+            color_type
+        ),
+    };
+    renderer.create_texture_rgba(info.width, info.height, &rgba)
+}
+
+fn read_png(path: &Path) -> Vec<u8> {
+    let file = File::open(path).unwrap_or_else(|e| {
+        panic!(
+            "{}: {}, run with UPDATE_SNAPSHOTS=1 to create it",
+            path.display(),
+            e
+        )
+    });
+    let mut reader = Decoder::new(file).read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).unwrap();
+    data.truncate(info.buffer_size());
+    data
+}
+
+fn write_png(path: &Path, data: &[u8]) {
+    let file = File::create(path).unwrap();
+    let mut encoder = Encoder::new(BufWriter::new(file), SCREEN_SIZE.0, SCREEN_SIZE.1);
+    encoder.set_color(ColorType::Rgb);
+    encoder.set_depth(BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(data).unwrap();
+}
+
+#[test]
+fn editor() {
+    let mut harness = Harness::new();
+    harness.send(Event::MouseMotion { x: 210, y: 270 });
+    harness.assert_snapshot("editor");
+}
+
+#[test]
+fn editor_with_layer_menu_and_minimap() {
+    let mut harness = Harness::new();
+    harness.key(Keycode::M);
+    harness.key(Keycode::L);
+    harness.key(Keycode::Num1);
+    harness.key(Keycode::Num9);
+    harness.assert_snapshot("editor_layer_menu_minimap");
+}
+
+#[test]
+fn editor_lighting_with_out_of_range_intensity() {
+    let mut harness = Harness::new();
+    // Level files can have any intensity, the editor only places 0-9
+    harness.context.level.spotlights.insert((100, 80), 20);
+    harness.key(Keycode::L);
+    harness.key(Keycode::Num9);
+    harness.key(Keycode::L);
+    harness.assert_snapshot("editor_lighting_out_of_range");
+}
+
+#[test]
+fn editor_zoomed_out() {
+    let mut harness = Harness::new();
+    harness.send(Event::MouseMotion { x: 320, y: 200 });
+    harness.key(Keycode::Minus);
+    harness.assert_snapshot("editor_zoomed_out");
+}
+
+#[test]
+fn tile_select() {
+    let mut harness = Harness::new();
+    harness.key(Keycode::Space);
+    harness.send(Event::MouseMotion { x: 50, y: 50 });
+    harness.assert_snapshot("tile_select");
+}
+
+#[test]
+fn help() {
+    let mut harness = Harness::new();
+    harness.key(Keycode::F1);
+    harness.assert_snapshot("help");
+}
+
+#[test]
+fn general_level_info() {
+    let mut harness = Harness::new();
+    harness.key(Keycode::F7);
+    harness.key(Keycode::Down);
+    harness.assert_snapshot("general_level_info");
+}
+
+#[test]
+fn random_item_editor() {
+    let mut harness = Harness::new();
+    harness.key(Keycode::F8);
+    harness.assert_snapshot("random_item_editor_normal");
+    harness.key(Keycode::Escape);
+    harness.key(Keycode::F9);
+    harness.key(Keycode::Down);
+    harness.assert_snapshot("random_item_editor_deathmatch");
+}
+
+#[test]
+fn load_level() {
+    let mut harness = Harness::new();
+    harness.key(Keycode::F3);
+    harness.key(Keycode::Down);
+    harness.assert_snapshot("load_level");
+}
+
+#[test]
+fn load_level_and_return_to_editor() {
+    let mut harness = Harness::new();
+    harness.key(Keycode::F3);
+    harness.key(Keycode::Return);
+    harness.click(5, 5);
+    harness.assert_snapshot("editor_after_load");
+}
+
+#[test]
+fn replayed_recording() {
+    let mut harness = Harness::new();
+    let mut level = Level::get_default_level((32, 22));
+    level.create_shadows(&ShadowRules::default());
+    harness.context.level = level;
+    harness.replay("place_tiles.txt");
+
+    let writes = MemoryLevelWriter::take_writes();
+    assert_eq!(writes.len(), 1);
+    assert_eq!(writes[0].0, "TILES.LEV");
+    harness.assert_snapshot("replayed_recording");
+}
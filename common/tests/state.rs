@@ -0,0 +1,283 @@
+use common::context::Context;
+use common::event::{Event, Keycode, Modifiers, MouseButton};
+use common::level::{Level, Steam, SHADOW_LOCK_EXTENSION};
+use common::render::Color;
+use common::software_renderer::{SoftwareRenderer, SoftwareTexture};
+use common::test_support::{
+    create_test_context, MemoryLevelLister, MemoryLevelWriter, RecordingTextInput, TextInputCall,
+};
+use common::types::TextureType;
+use common::{RunState, State};
+
+struct Editor {
+    state: State<MemoryLevelWriter>,
+    context: Context<MemoryLevelLister, SoftwareTexture>,
+    text_input: RecordingTextInput,
+}
+
+impl Editor {
+    fn new() -> Self {
+        let mut renderer = SoftwareRenderer::new(640, 400);
+        Editor {
+            state: State::new(),
+            context: create_test_context(&mut renderer, Level::get_default_level((16, 12))),
+            text_input: RecordingTextInput::default(),
+        }
+    }
+
+    fn send(&mut self, event: Event) -> RunState {
+        self.state
+            .handle_event(&mut self.context, &mut self.text_input, event)
+    }
+
+    fn key_with(&mut self, keycode: Keycode, modifiers: Modifiers) -> RunState {
+        self.send(Event::KeyDown { keycode, modifiers })
+    }
+
+    fn key(&mut self, keycode: Keycode) -> RunState {
+        self.key_with(keycode, Modifiers::default())
+    }
+
+    fn save_as(&mut self, name: &str) {
+        self.key_with(
+            Keycode::S,
+            Modifiers {
+                ctrl: true,
+                ..Modifiers::default()
+            },
+        );
+        self.send(Event::TextInput {
+            text: name.to_string(),
+        });
+        self.key(Keycode::Return);
+    }
+}
+
+#[test]
+fn saving_writes_level_and_stops_text_input() {
+    let mut editor = Editor::new();
+    editor.context.unsaved_changes = true;
+    editor.save_as("test");
+
+    let writes = MemoryLevelWriter::take_writes();
+    assert_eq!(writes.len(), 1);
+    assert_eq!(writes[0].0, "TEST.LEV");
+    assert!(writes[0].1 == editor.context.level.serialize());
+    assert_eq!(
+        editor.text_input.calls,
+        [TextInputCall::Start, TextInputCall::Stop]
+    );
+    assert_eq!(editor.context.saved_level_name.as_deref(), Some("test.lev"));
+    assert!(!editor.context.unsaved_changes);
+}
+
+#[test]
+fn saving_writes_shadow_locks_next_to_level() {
+    let mut editor = Editor::new();
+    editor
+        .context
+        .level
+        .put_tile_to_level(20, Some(1), &TextureType::Shadow);
+    editor.save_as("locks");
+
+    let names = MemoryLevelWriter::take_writes()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "LOCKS.LEV".to_string(),
+            format!("LOCKS.{}", SHADOW_LOCK_EXTENSION)
+        ]
+    );
+}
+
+#[test]
+fn saving_without_shadow_locks_removes_old_sidecar() {
+    let mut editor = Editor::new();
+    editor
+        .context
+        .level
+        .put_tile_to_level(20, Some(1), &TextureType::Shadow);
+    editor.save_as("locks");
+    assert!(MemoryLevelWriter::take_removals().is_empty());
+
+    editor.context.level.shadow_locks.clear();
+    editor.save_as("locks");
+    let names = MemoryLevelWriter::take_writes()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    assert_eq!(names.last().map(String::as_str), Some("LOCKS.LEV"));
+    assert_eq!(
+        MemoryLevelWriter::take_removals(),
+        [format!("LOCKS.{}", SHADOW_LOCK_EXTENSION)]
+    );
+}
+
+#[test]
+fn failed_save_keeps_unsaved_changes_and_asks_for_another_name() {
+    let mut editor = Editor::new();
+    editor.context.unsaved_changes = true;
+    MemoryLevelWriter::set_failing(true);
+    editor.save_as("test");
+
+    assert!(MemoryLevelWriter::take_writes().is_empty());
+    assert!(editor.context.unsaved_changes);
+    assert_eq!(editor.context.saved_level_name, None);
+    assert!(!editor.text_input.is_active());
+
+    MemoryLevelWriter::set_failing(false);
+    editor.key(Keycode::Y);
+    assert!(editor.text_input.is_active());
+    editor.send(Event::TextInput {
+        text: "2".to_string(),
+    });
+    editor.key(Keycode::Return);
+    assert_eq!(MemoryLevelWriter::take_writes()[0].0, "TEST2.LEV");
+    assert!(!editor.context.unsaved_changes);
+}
+
+#[test]
+fn loading_level_replaces_edited_level() {
+    let mut editor = Editor::new();
+    editor
+        .context
+        .level_lister
+        .add_level("BIG.LEV", Level::get_default_level((40, 30)).serialize());
+    editor.key(Keycode::F3);
+    assert_eq!(editor.context.level_lister.refresh_count, 1);
+    editor.key(Keycode::Return);
+
+    assert_eq!(editor.context.level.tiles.len(), 30);
+    assert_eq!(editor.context.saved_level_name.as_deref(), Some("BIG.LEV"));
+    assert_eq!(editor.context.level_save_name, "BIG");
+}
+
+#[test]
+fn failed_load_keeps_edited_level() {
+    let mut editor = Editor::new();
+    editor
+        .context
+        .level_lister
+        .add_level("BIG.LEV", Level::get_default_level((40, 30)).serialize());
+    editor.context.level_lister.fail_loads = true;
+    editor.context.unsaved_changes = true;
+    editor.key(Keycode::F3);
+    editor.key(Keycode::Return);
+
+    assert_eq!(editor.context.level.tiles.len(), 12);
+    assert!(editor.context.unsaved_changes);
+    assert_eq!(editor.context.saved_level_name, None);
+}
+
+#[test]
+fn escape_and_confirm_quits() {
+    let mut editor = Editor::new();
+    assert!(matches!(
+        editor.key(Keycode::Escape),
+        RunState::Run { needs_render: true }
+    ));
+    assert!(matches!(editor.key(Keycode::Y), RunState::Quit));
+}
+
+#[test]
+fn ticks_are_needed_only_while_steam_animates() {
+    let mut editor = Editor::new();
+    assert!(!editor.state.needs_ticks(&editor.context));
+    editor.key(Keycode::L);
+    editor.key(Keycode::Num0);
+    assert!(!editor.state.needs_ticks(&editor.context));
+
+    editor.context.level.put_steam_to_level(
+        &(100, 100),
+        &Steam {
+            range: 3,
+            angle: 90,
+        },
+    );
+    assert!(editor.state.needs_ticks(&editor.context));
+    editor.key(Keycode::F1);
+    assert!(!editor.state.needs_ticks(&editor.context));
+}
+
+#[test]
+fn shift_wheel_scrolls_horizontally_also_from_horizontal_wheel() {
+    let shift = Modifiers {
+        shift: true,
+        ..Modifiers::default()
+    };
+    for (x, y) in [(0, -1), (-1, 0)] {
+        let mut editor = Editor::new();
+        editor.context.level = Level::get_default_level((60, 40));
+        editor.send(Event::MouseWheel {
+            x,
+            y,
+            modifiers: shift,
+        });
+        assert!(editor.context.viewport.0 > 0);
+        assert_eq!(editor.context.viewport.1, 0);
+    }
+}
+
+#[test]
+fn steam_range_is_aimed_in_level_pixels() {
+    let mut editor = Editor::new();
+    editor.context.graphics.zoom = 200;
+    editor.key(Keycode::A);
+    editor.send(Event::MouseMotion { x: 100, y: 100 });
+    editor.send(Event::MouseButtonDown {
+        button: MouseButton::Left,
+        modifiers: Modifiers::default(),
+    });
+    // 60 screen pixels are 30 level pixels at 200% zoom, one range step
+    editor.send(Event::MouseMotion { x: 100, y: 160 });
+    editor.send(Event::MouseButtonUp {
+        button: MouseButton::Left,
+    });
+    let steam = editor.context.level.get_steam_from_level(&(50, 50));
+    assert_eq!(steam.range, 1);
+    assert_eq!(steam.angle, 0);
+
+    // The furthest render circle is centered where the mouse aimed and has a radius of 15 pixels
+    let mut renderer = SoftwareRenderer::new(640, 400);
+    editor.state.render(&mut renderer, &editor.context);
+    let red = Color::from((255, 0, 0)).to_u32();
+    let lowest_red = (100..300)
+        .filter(|y| renderer.pixels()[y * 640 + 100] == red)
+        .max();
+    assert!(lowest_red.is_some_and(|y| (173..=175).contains(&y)));
+}
+
+#[test]
+fn inspector_takes_keys_only_with_focus() {
+    let mut editor = Editor::new();
+    editor.context.level = Level::get_default_level((60, 40));
+    editor.key(Keycode::Q);
+    editor.send(Event::MouseMotion { x: 100, y: 100 });
+    editor.send(Event::MouseButtonDown {
+        button: MouseButton::Left,
+        modifiers: Modifiers::default(),
+    });
+    editor.send(Event::MouseButtonUp {
+        button: MouseButton::Left,
+    });
+    assert_eq!(editor.context.level.spotlights.get(&(100, 100)), Some(&0));
+
+    // Placed object is selected but the arrow keys still scroll the level
+    editor.key(Keycode::Right);
+    assert!(editor.context.viewport.0 > 0);
+    assert_eq!(editor.context.level.spotlights.get(&(100, 100)), Some(&0));
+
+    editor.key(Keycode::Tab);
+    let viewport = editor.context.viewport;
+    editor.key(Keycode::Right);
+    assert_eq!(editor.context.viewport, viewport);
+    assert_eq!(editor.context.level.spotlights.get(&(100, 100)), Some(&1));
+
+    editor.key(Keycode::Escape);
+    editor.key(Keycode::Right);
+    assert!(editor.context.viewport.0 > viewport.0);
+    assert_eq!(editor.context.level.spotlights.get(&(100, 100)), Some(&1));
+}
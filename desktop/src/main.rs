@@ -1,24 +1,34 @@
 mod render;
 
 use sdl2::image::InitFlag;
-use sdl2::keyboard::TextInputUtil;
+use sdl2::keyboard::{Mod, TextInputUtil};
+use sdl2::mouse::MouseWheelDirection;
+use std::env;
 use std::fs;
 use std::fs::File;
 use std::io::{Read, Write};
+use std::path::Path;
+use std::time::Instant;
 
 use crate::render::{SdlRenderer, SdlTexture};
 use common::context::{Context, Textures};
 use common::editor::LevelWriter;
-use common::event::{Event, Keycode, MouseButton, WindowEvent};
+use common::event::{Event, Keycode, Modifiers, MouseButton, WindowEvent, TICK_INTERVAL_MS};
 use common::fn2::FN2;
 use common::font::Font;
 use common::graphics::Graphics;
 use common::level::Level;
 use common::load_level::LevelLister;
-use common::render::Renderer;
+use common::recording::{format_header, format_line};
+use common::render::{create_crate_icons, create_light_texture, Renderer};
+use common::shadow_rules::ShadowRules;
 use common::types::{TextureType, Trigonometry};
+use common::util::get_number_of_tiles_in_texture;
 use common::{RunState, State, TextInput};
 
+// Optional sprite sheet replacing the generated crate icons
+const CRATE_ICONS_PATH: &str = "assets/CRATES.PNG";
+
 struct SdlTextInput(TextInputUtil);
 
 impl TextInput for SdlTextInput {
@@ -32,6 +42,11 @@ impl TextInput for SdlTextInput {
 }
 
 pub fn main() {
+    // Events can be recorded for replaying them with utk-lev replay
+    let mut recording = env::args()
+        .skip_while(|arg| arg != "--record")
+        .nth(1)
+        .map(|path| File::create(&path).unwrap_or_else(|e| panic!("{}: {}", path, e)));
     let sdl = sdl2::init().unwrap();
     let _image_context = sdl2::image::init(InitFlag::PNG);
     let video_subsystem = sdl.video().unwrap();
@@ -59,7 +74,21 @@ pub fn main() {
         FN2::parse(&font_data)
     };
     let font = Font::new(&mut renderer, &fn2, 2);
-    let textures = get_textures(&mut renderer);
+    let textures = get_textures(&mut renderer, &fn2);
+    let shadow_rules = match fs::read_to_string("assets/SHADOW_RULES.TXT") {
+        Ok(text) => ShadowRules::parse(&text)
+            .and_then(|rules| {
+                rules.check_shadows(get_number_of_tiles_in_texture(&textures.shadows))
+            })
+            .unwrap_or_else(|e| {
+                eprintln!(
+                    "Invalid assets/SHADOW_RULES.TXT ({:?}), using the default rules",
+                    e
+                );
+                ShadowRules::default()
+            }),
+        Err(_) => ShadowRules::default(),
+    };
     let mut context = Context {
         graphics,
         fn2,
@@ -71,21 +100,60 @@ pub fn main() {
         texture_type_selected: TextureType::Floor,
         texture_type_scrolled: TextureType::Floor,
         mouse: (0, 0),
+        viewport: (0, 0),
         level_save_name: String::new(),
         saved_level_name: None,
+        unsaved_changes: false,
         trigonometry: Trigonometry::new(),
         automatic_shadows: true,
+        shadow_rules,
     };
+    context.level.create_shadows(&context.shadow_rules);
     let mut text_input = SdlTextInput(video_subsystem.text_input());
 
     let mut state: State<FileLevelWriter> = State::new();
+    let recording_start = Instant::now();
+    if let Some(file) = &mut recording {
+        file.write_all(
+            format_header(renderer.window_size(), context.graphics.render_multiplier).as_bytes(),
+        )
+        .unwrap();
+    }
+    let mut last_tick = Instant::now();
     loop {
-        let sdl_event = event_pump.wait_event();
-        if let Some(event) = convert_event(sdl_event) {
-            let mut render = false;
+        let needs_ticks = state.needs_ticks(&context);
+        let sdl_event = if needs_ticks {
+            event_pump.wait_event_timeout(TICK_INTERVAL_MS)
+        } else {
+            Some(event_pump.wait_event())
+        };
+        let mut events: Vec<Event> = sdl_event
+            .and_then(|sdl_event| convert_event(sdl_event, sdl.keyboard().mod_state()))
+            .into_iter()
+            .collect();
+        let milliseconds = last_tick.elapsed().as_millis() as u32;
+        if !needs_ticks {
+            // The first tick after the animation starts counts from here, not from the last one
+            last_tick = Instant::now();
+        } else if milliseconds >= TICK_INTERVAL_MS {
+            last_tick = Instant::now();
+            events.push(Event::Tick { milliseconds });
+        }
+        let mut render = false;
+        for mut event in events {
             if let Event::Window { win_event } = event {
                 resize(&mut renderer, &mut context, win_event);
-                render = true
+                render = true;
+                // Replays do not know the size of a maximized window
+                let (width, height) = renderer.window_size();
+                event = Event::Window {
+                    win_event: WindowEvent::Resized { width, height },
+                };
+            }
+            if let Some(file) = &mut recording {
+                let milliseconds = recording_start.elapsed().as_millis() as u64;
+                file.write_all(format_line(milliseconds, &event).as_bytes())
+                    .unwrap();
             }
             match state.handle_event(&mut context, &mut text_input, event) {
                 RunState::Quit => return,
@@ -94,10 +162,10 @@ pub fn main() {
                 }
                 _ => {}
             }
-            if render {
-                state.render(&mut renderer, &context);
-                renderer.present();
-            }
+        }
+        if render {
+            state.render(&mut renderer, &context);
+            renderer.present();
         }
     }
 }
@@ -110,7 +178,7 @@ fn refresh(
     context.graphics.resolution_x = window_size.0;
     context.graphics.resolution_y = window_size.1;
     context.font = Font::new(renderer, &context.fn2, 2);
-    context.textures = get_textures(renderer);
+    context.textures = get_textures(renderer, &context.fn2);
 }
 
 fn resize(
@@ -128,15 +196,21 @@ fn resize(
     }
 }
 
-fn get_textures(renderer: &mut SdlRenderer) -> Textures<SdlTexture> {
+fn get_textures(renderer: &mut SdlRenderer, fn2: &FN2) -> Textures<SdlTexture> {
     Textures {
         floor: renderer.load_texture("assets/FLOOR1.PNG"),
         walls: renderer.load_texture("assets/WALLS1.PNG"),
         shadows: renderer.load_texture("assets/SHADOWS_ALPHA.PNG"),
+        light: create_light_texture(renderer),
+        crates: if Path::new(CRATE_ICONS_PATH).exists() {
+            renderer.load_texture(CRATE_ICONS_PATH)
+        } else {
+            create_crate_icons(renderer, fn2)
+        },
     }
 }
 
-fn convert_event(event: sdl2::event::Event) -> Option<Event> {
+fn convert_event(event: sdl2::event::Event, keymod: Mod) -> Option<Event> {
     use sdl2::event::Event as SdlEvent;
     use sdl2::event::WindowEvent as SdlWindowEvent;
 
@@ -162,10 +236,17 @@ fn convert_event(event: sdl2::event::Event) -> Option<Event> {
         },
         SdlEvent::KeyDown {
             keycode: Some(sdl_keycode),
+            keymod,
             ..
-        } => convert_keycode(sdl_keycode).map(|keycode| Event::KeyDown { keycode }),
+        } => convert_keycode(sdl_keycode).map(|keycode| Event::KeyDown {
+            keycode,
+            modifiers: convert_modifiers(keymod),
+        }),
         SdlEvent::MouseButtonDown { mouse_btn, .. } => {
-            convert_mouse_button(mouse_btn).map(|button| Event::MouseButtonDown { button })
+            convert_mouse_button(mouse_btn).map(|button| Event::MouseButtonDown {
+                button,
+                modifiers: convert_modifiers(keymod),
+            })
         }
         SdlEvent::MouseButtonUp { mouse_btn, .. } => {
             convert_mouse_button(mouse_btn).map(|button| Event::MouseButtonUp { button })
@@ -180,6 +261,19 @@ fn convert_event(event: sdl2::event::Event) -> Option<Event> {
                 None
             }
         }
+        SdlEvent::MouseWheel {
+            x, y, direction, ..
+        } => {
+            let (x, y) = match direction {
+                MouseWheelDirection::Flipped => (-x, -y),
+                _ => (x, y),
+            };
+            Some(Event::MouseWheel {
+                x,
+                y,
+                modifiers: convert_modifiers(keymod),
+            })
+        }
         SdlEvent::TextInput { text, .. } => Some(Event::TextInput { text }),
         _ => None,
     }
@@ -190,8 +284,13 @@ fn convert_keycode(keycode: sdl2::keyboard::Keycode) -> Option<Keycode> {
     match keycode {
         SdlKeycode::Escape => Some(Keycode::Escape),
         SdlKeycode::Backspace => Some(Keycode::Backspace),
+        SdlKeycode::Tab => Some(Keycode::Tab),
         SdlKeycode::Return => Some(Keycode::Return),
         SdlKeycode::Space => Some(Keycode::Space),
+        SdlKeycode::Delete => Some(Keycode::Delete),
+        SdlKeycode::Insert => Some(Keycode::Insert),
+        SdlKeycode::Home => Some(Keycode::Home),
+        SdlKeycode::End => Some(Keycode::End),
         SdlKeycode::PageDown => Some(Keycode::PageDown),
         SdlKeycode::PageUp => Some(Keycode::PageUp),
         SdlKeycode::Up => Some(Keycode::Up),
@@ -204,30 +303,69 @@ fn convert_keycode(keycode: sdl2::keyboard::Keycode) -> Option<Keycode> {
         SdlKeycode::Minus => Some(Keycode::Minus),
         SdlKeycode::Plus => Some(Keycode::Plus),
         SdlKeycode::A => Some(Keycode::A),
+        SdlKeycode::B => Some(Keycode::B),
         SdlKeycode::C => Some(Keycode::C),
+        SdlKeycode::D => Some(Keycode::D),
+        SdlKeycode::E => Some(Keycode::E),
+        SdlKeycode::F => Some(Keycode::F),
+        SdlKeycode::G => Some(Keycode::G),
+        SdlKeycode::H => Some(Keycode::H),
+        SdlKeycode::I => Some(Keycode::I),
+        SdlKeycode::J => Some(Keycode::J),
+        SdlKeycode::K => Some(Keycode::K),
+        SdlKeycode::L => Some(Keycode::L),
+        SdlKeycode::M => Some(Keycode::M),
+        SdlKeycode::N => Some(Keycode::N),
+        SdlKeycode::O => Some(Keycode::O),
+        SdlKeycode::P => Some(Keycode::P),
         SdlKeycode::Q => Some(Keycode::Q),
+        SdlKeycode::R => Some(Keycode::R),
         SdlKeycode::S => Some(Keycode::S),
+        SdlKeycode::T => Some(Keycode::T),
+        SdlKeycode::U => Some(Keycode::U),
+        SdlKeycode::V => Some(Keycode::V),
         SdlKeycode::W => Some(Keycode::W),
         SdlKeycode::X => Some(Keycode::X),
         SdlKeycode::Y => Some(Keycode::Y),
         SdlKeycode::Z => Some(Keycode::Z),
+        SdlKeycode::Num0 => Some(Keycode::Num0),
         SdlKeycode::Num1 => Some(Keycode::Num1),
         SdlKeycode::Num2 => Some(Keycode::Num2),
+        SdlKeycode::Num3 => Some(Keycode::Num3),
+        SdlKeycode::Num4 => Some(Keycode::Num4),
+        SdlKeycode::Num5 => Some(Keycode::Num5),
+        SdlKeycode::Num6 => Some(Keycode::Num6),
+        SdlKeycode::Num7 => Some(Keycode::Num7),
+        SdlKeycode::Num8 => Some(Keycode::Num8),
+        SdlKeycode::Num9 => Some(Keycode::Num9),
         SdlKeycode::F1 => Some(Keycode::F1),
         SdlKeycode::F2 => Some(Keycode::F2),
         SdlKeycode::F3 => Some(Keycode::F3),
         SdlKeycode::F4 => Some(Keycode::F4),
+        SdlKeycode::F5 => Some(Keycode::F5),
         SdlKeycode::F6 => Some(Keycode::F6),
         SdlKeycode::F7 => Some(Keycode::F7),
         SdlKeycode::F8 => Some(Keycode::F8),
         SdlKeycode::F9 => Some(Keycode::F9),
+        SdlKeycode::F10 => Some(Keycode::F10),
+        SdlKeycode::F11 => Some(Keycode::F11),
+        SdlKeycode::F12 => Some(Keycode::F12),
         _ => None,
     }
 }
 
+fn convert_modifiers(keymod: Mod) -> Modifiers {
+    Modifiers {
+        shift: keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD),
+        ctrl: keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD),
+        alt: keymod.intersects(Mod::LALTMOD | Mod::RALTMOD),
+    }
+}
+
 fn convert_mouse_button(button: sdl2::mouse::MouseButton) -> Option<MouseButton> {
     match button {
         sdl2::mouse::MouseButton::Left => Some(MouseButton::Left),
+        sdl2::mouse::MouseButton::Middle => Some(MouseButton::Middle),
         sdl2::mouse::MouseButton::Right => Some(MouseButton::Right),
         _ => None,
     }
@@ -258,6 +396,8 @@ impl LevelLister for DirectoryLevelLister {
                 }
             })
             .collect();
+        // Same order on every run, so that recorded level selections replay the same level
+        self.files.sort();
     }
 
     fn reset(&mut self) {}
@@ -276,13 +416,31 @@ impl LevelLister for DirectoryLevelLister {
         file.read_to_end(&mut buffer).unwrap();
         buffer
     }
+
+    fn load_sidecar(&self, index: usize, extension: &str) -> Option<Vec<u8>> {
+        let level_name = &self.files[index];
+        let stem = &level_name[..level_name.len() - ".LEV".len()];
+        // The editor saves sidecars with upper case names, which may differ from the level's name
+        let sidecar_name = format!("{}.{}", stem, extension).to_uppercase();
+        let sidecar = fs::read_dir("./")
+            .ok()?
+            .filter_map(|entry_result| entry_result.ok()?.file_name().into_string().ok())
+            .find(|filename| filename.to_uppercase() == sidecar_name)?;
+        fs::read(sidecar).ok()
+    }
 }
 
 struct FileLevelWriter;
 
 impl LevelWriter for FileLevelWriter {
-    fn write(filename: &str, level_data: &[u8]) {
-        let mut file = File::create(filename).unwrap();
-        file.write_all(level_data).unwrap();
+    fn write(filename: &str, level_data: &[u8]) -> std::io::Result<()> {
+        File::create(filename)?.write_all(level_data)
+    }
+
+    fn remove(filename: &str) -> std::io::Result<()> {
+        match fs::remove_file(filename) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
     }
 }
@@ -1,6 +1,7 @@
 use common::render::{Color, Point, Rect, Renderer, RendererColor, Texture};
 use sdl2::image::LoadTexture;
 use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::BlendMode;
 use sdl2::render::TextureQuery;
 use sdl2::render::{Canvas, TextureCreator};
 use sdl2::surface::Surface;
@@ -174,6 +175,24 @@ impl<'a> Renderer for SdlRenderer<'a> {
             .unwrap();
     }
 
+    fn fill_rect_blended(&mut self, rect: &Rect, color: Color) {
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas.set_draw_color(sdl2::pixels::Color::RGBA(
+            color.r, color.g, color.b, color.a,
+        ));
+        self.canvas.fill_rect(Some(to_sdl_rect(*rect))).unwrap();
+        self.canvas.set_blend_mode(BlendMode::None);
+    }
+
+    fn render_texture_additive(&mut self, texture: &Self::Texture, dst: Rect, strength: u8) {
+        let t = self.textures.get_mut(&texture.index).unwrap();
+        t.set_blend_mode(BlendMode::Add);
+        t.set_alpha_mod(strength);
+        self.canvas.copy(t, None, Some(to_sdl_rect(dst))).unwrap();
+        t.set_alpha_mod(255);
+        t.set_blend_mode(BlendMode::Blend);
+    }
+
     fn window_size(&self) -> (u32, u32) {
         self.canvas.window().size()
     }
@@ -2,17 +2,20 @@ import init, {
   LevelEditor,
   WebImage,
   Keycode,
+  Modifiers,
   MouseButton,
 } from "./utk-level-editor.js"
 
 export async function run() {
   const wasm = await init()
-  const [floor1, walls1, shadowsAlpha, tetrisFn2] = await Promise.all([
-    loadImage("FLOOR1.PNG"),
-    loadImage("WALLS1.PNG"),
-    loadImage("SHADOWS_ALPHA.PNG"),
-    loadFile("TETRIS.FN2"),
-  ])
+  const [floor1, walls1, shadowsAlpha, tetrisFn2, shadowRules] =
+    await Promise.all([
+      loadImage("FLOOR1.PNG"),
+      loadImage("WALLS1.PNG"),
+      loadImage("SHADOWS_ALPHA.PNG"),
+      loadFile("TETRIS.FN2"),
+      loadOptionalText("SHADOW_RULES.TXT"),
+    ])
 
   const fileUpload = initFileUpload("#file-upload", (files) => {
     for (const [name, data] of files) {
@@ -26,17 +29,49 @@ export async function run() {
     walls1,
     shadowsAlpha,
     tetrisFn2,
+    shadowRules,
     fileUpload.show,
     fileUpload.hide
   )
 
+  // Events are recorded with ?record in the URL, for replaying them with utk-lev replay
+  if (new URLSearchParams(location.search).has("record")) {
+    state.start_recording()
+    document.getElementById("recording").style.display = "inline"
+    const link = document.getElementById("download-recording")
+    link.addEventListener("click", () => {
+      const file = new File([state.recording()], "recording.txt")
+      link.href = URL.createObjectURL(file)
+    })
+  }
+
   const canvas = document.getElementById("screen")
   canvas.width = state.screen_width()
   canvas.height = state.screen_height()
   const context = canvas.getContext("2d")
 
+  // Ticks run only while something animates, every change to that also renders a frame
+  let tickTimer = null
+  let lastTick = 0
+  const updateTicks = () => {
+    if (state.needs_ticks() === (tickTimer !== null)) return
+    if (tickTimer === null) {
+      lastTick = performance.now()
+      tickTimer = setInterval(() => {
+        const now = performance.now()
+        const needsRender = state.tick(Math.round(now - lastTick))
+        lastTick = now
+        if (needsRender) renderFrame()
+      }, state.tick_interval())
+    } else {
+      clearInterval(tickTimer)
+      tickTimer = null
+    }
+  }
+
   let frameId = null
   const renderFrame = () => {
+    updateTicks()
     if (frameId !== null) cancelAnimationFrame(frameId)
     frameId = requestAnimationFrame(() => {
       state.frame()
@@ -53,12 +88,16 @@ export async function run() {
   )
 
   document.addEventListener("keydown", (event) => {
+    const shortcut = event.ctrlKey || event.metaKey || event.altKey
     const keycode = toKeycode(event.key)
     const text =
-      event.key.length === 1 && textKeys.has(event.key) ? event.key : undefined
+      !shortcut && event.key.length === 1 && textKeys.has(event.key)
+        ? event.key
+        : undefined
     if (keycode !== undefined || text !== undefined) {
-      event.preventDefault()
-      const needsRender = state.key_down(keycode, text)
+      const needsRender = state.key_down(keycode, text, toModifiers(event))
+      // Leave unused shortcuts such as Ctrl+R to the browser
+      if (needsRender || !shortcut) event.preventDefault()
       if (needsRender) renderFrame()
     }
   })
@@ -70,22 +109,35 @@ export async function run() {
     if (needsRender) renderFrame()
   })
   const mouseButton = (handler) => (event) => {
+    // Middle button would start the browser's autoscroll
+    if (event.button === 1) event.preventDefault()
     const needsRender =
       event.button === 0
-        ? handler(MouseButton.Left)
+        ? handler(MouseButton.Left, event)
+        : event.button === 1
+        ? handler(MouseButton.Middle, event)
         : event.button === 2
-        ? handler(MouseButton.Right)
+        ? handler(MouseButton.Right, event)
         : false
     if (needsRender) renderFrame()
   }
   canvas.addEventListener(
     "mousedown",
-    mouseButton((button) => state.mouse_down(button))
+    mouseButton((button, event) => state.mouse_down(button, toModifiers(event)))
   )
   canvas.addEventListener(
     "mouseup",
     mouseButton((button) => state.mouse_up(button))
   )
+  canvas.addEventListener("wheel", (event) => {
+    event.preventDefault()
+    const needsRender = state.mouse_wheel(
+      -Math.sign(event.deltaX),
+      -Math.sign(event.deltaY),
+      toModifiers(event)
+    )
+    if (needsRender) renderFrame()
+  })
   canvas.addEventListener("contextmenu", (event) => {
     event.preventDefault()
     return false
@@ -183,14 +235,32 @@ async function loadFile(url) {
   return new Uint8Array(arrayBuffer)
 }
 
+async function loadOptionalText(url) {
+  const response = await fetch(url)
+  return response.ok ? await response.text() : undefined
+}
+
 function toKeycode(key) {
+  if (/^[a-zA-Z]$/.test(key)) return Keycode[key.toUpperCase()]
+  if (/^[0-9]$/.test(key)) return Keycode[`Num${key}`]
+  if (/^F([1-9]|1[0-2])$/.test(key)) return Keycode[key]
   switch (key) {
     case "Escape":
       return Keycode.Escape
     case "Backspace":
       return Keycode.Backspace
+    case "Tab":
+      return Keycode.Tab
     case "Enter":
       return Keycode.Return
+    case "Delete":
+      return Keycode.Delete
+    case "Insert":
+      return Keycode.Insert
+    case "Home":
+      return Keycode.Home
+    case "End":
+      return Keycode.End
     case "ArrowLeft":
       return Keycode.Left
     case "ArrowUp":
@@ -203,44 +273,6 @@ function toKeycode(key) {
       return Keycode.PageUp
     case "PageDown":
       return Keycode.PageDown
-    case "1":
-      return Keycode.Num1
-    case "2":
-      return Keycode.Num2
-    case "a":
-      return Keycode.A
-    case "c":
-      return Keycode.C
-    case "e":
-      return Keycode.E
-    case "q":
-      return Keycode.Q
-    case "s":
-      return Keycode.S
-    case "w":
-      return Keycode.W
-    case "x":
-      return Keycode.X
-    case "y":
-      return Keycode.Y
-    case "z":
-      return Keycode.Z
-    case "F1":
-      return Keycode.F1
-    case "F2":
-      return Keycode.F2
-    case "F3":
-      return Keycode.F3
-    case "F4":
-      return Keycode.F4
-    case "F6":
-      return Keycode.F6
-    case "F7":
-      return Keycode.F7
-    case "F8":
-      return Keycode.F8
-    case "F9":
-      return Keycode.F9
     case " ":
       return Keycode.Space
     case "+":
@@ -251,3 +283,11 @@ function toKeycode(key) {
       return undefined
   }
 }
+
+function toModifiers(event) {
+  return Modifiers.new(
+    event.shiftKey,
+    event.ctrlKey || event.metaKey,
+    event.altKey
+  )
+}
@@ -1,17 +1,19 @@
-mod render;
-
 use wasm_bindgen::prelude::wasm_bindgen;
 
-use crate::render::{CanvasRenderer, CanvasTexture};
 use common::context::{Context, Textures};
 use common::editor::LevelWriter;
-use common::event::Event;
+use common::event::{Event, TICK_INTERVAL_MS};
 use common::fn2::FN2;
 use common::font::Font;
 use common::graphics::Graphics;
 use common::level::Level;
 use common::load_level::LevelLister;
+use common::recording::{format_header, format_line};
+use common::render::{create_crate_icons, create_light_texture};
+use common::shadow_rules::ShadowRules;
+use common::software_renderer::{SoftwareRenderer, SoftwareTexture};
 use common::types::{TextureType, Trigonometry};
+use common::util::get_number_of_tiles_in_texture;
 use common::{RunState, State, TextInput};
 use log::Level as LogLevel;
 use wasm_bindgen::JsValue;
@@ -36,10 +38,11 @@ impl WebImage {
 
 #[wasm_bindgen]
 pub struct LevelEditor {
-    renderer: CanvasRenderer,
+    renderer: SoftwareRenderer,
     state: State<WebLevelWriter>,
-    context: Context<WebLevelLister, CanvasTexture>,
+    context: Context<WebLevelLister, SoftwareTexture>,
     text_input: WebTextInput,
+    recording: Option<(f64, String)>, // start time and recorded text
 }
 
 #[wasm_bindgen]
@@ -49,6 +52,7 @@ impl LevelEditor {
         walls_texture: WebImage,
         shadows_alpha_texture: WebImage,
         font_data: &[u8],
+        shadow_rules: Option<String>,
         show_file_upload: js_sys::Function,
         hide_file_upload: js_sys::Function,
     ) -> Self {
@@ -59,7 +63,7 @@ impl LevelEditor {
 
         let graphics = Graphics::new((width, height), 1);
 
-        let mut renderer = CanvasRenderer::new(width, height);
+        let mut renderer = SoftwareRenderer::new(width, height);
         let fn2 = FN2::parse(font_data);
         let font = Font::new(&mut renderer, &fn2, 1);
         let textures = Textures {
@@ -78,9 +82,25 @@ impl LevelEditor {
                 shadows_alpha_texture.height,
                 &shadows_alpha_texture.data,
             ),
+            light: create_light_texture(&mut renderer),
+            crates: create_crate_icons(&mut renderer, &fn2),
         };
 
-        let context = Context {
+        let shadow_rules = match shadow_rules {
+            Some(text) => ShadowRules::parse(&text)
+                .and_then(|rules| {
+                    rules.check_shadows(get_number_of_tiles_in_texture(&textures.shadows))
+                })
+                .unwrap_or_else(|e| {
+                    log::error!(
+                        "Invalid SHADOW_RULES.TXT ({:?}), using the default rules",
+                        e
+                    );
+                    ShadowRules::default()
+                }),
+            None => ShadowRules::default(),
+        };
+        let mut context = Context {
             graphics,
             fn2,
             font,
@@ -91,11 +111,15 @@ impl LevelEditor {
             texture_type_selected: TextureType::Floor,
             texture_type_scrolled: TextureType::Floor,
             mouse: (0, 0),
+            viewport: (0, 0),
             level_save_name: String::new(),
             saved_level_name: None,
+            unsaved_changes: false,
             trigonometry: Trigonometry::new(),
             automatic_shadows: true,
+            shadow_rules,
         };
+        context.level.create_shadows(&context.shadow_rules);
         let text_input = WebTextInput { enabled: false };
         let state: State<WebLevelWriter> = State::new();
         Self {
@@ -103,11 +127,12 @@ impl LevelEditor {
             state,
             context,
             text_input,
+            recording: None,
         }
     }
 
     pub fn screen(&self) -> *const u32 {
-        self.renderer.pixels()
+        self.renderer.pixels().as_ptr()
     }
     pub fn screen_width(&self) -> u32 {
         self.renderer.width()
@@ -116,7 +141,22 @@ impl LevelEditor {
         self.renderer.height()
     }
 
+    pub fn start_recording(&mut self) {
+        let header = format_header(
+            (self.renderer.width(), self.renderer.height()),
+            self.context.graphics.render_multiplier,
+        );
+        self.recording = Some((js_sys::Date::now(), header));
+    }
+
+    pub fn recording(&self) -> Option<String> {
+        self.recording.as_ref().map(|(_, text)| text.clone())
+    }
+
     fn handle_event(&mut self, event: Event) -> bool {
+        if let Some((start, text)) = &mut self.recording {
+            text.push_str(&format_line((js_sys::Date::now() - *start) as u64, &event));
+        }
         let run_state = self
             .state
             .handle_event(&mut self.context, &mut self.text_input, event);
@@ -129,9 +169,10 @@ impl LevelEditor {
     pub fn mouse_move(&mut self, x: u32, y: u32) -> bool {
         self.handle_event(Event::MouseMotion { x, y })
     }
-    pub fn mouse_down(&mut self, button: MouseButton) -> bool {
+    pub fn mouse_down(&mut self, button: MouseButton, modifiers: Modifiers) -> bool {
         self.handle_event(Event::MouseButtonDown {
             button: button.into(),
+            modifiers: modifiers.into(),
         })
     }
     pub fn mouse_up(&mut self, button: MouseButton) -> bool {
@@ -139,7 +180,19 @@ impl LevelEditor {
             button: button.into(),
         })
     }
-    pub fn key_down(&mut self, key: Option<Keycode>, text: Option<String>) -> bool {
+    pub fn mouse_wheel(&mut self, x: i32, y: i32, modifiers: Modifiers) -> bool {
+        self.handle_event(Event::MouseWheel {
+            x,
+            y,
+            modifiers: modifiers.into(),
+        })
+    }
+    pub fn key_down(
+        &mut self,
+        key: Option<Keycode>,
+        text: Option<String>,
+        modifiers: Modifiers,
+    ) -> bool {
         if self.text_input.enabled {
             if let Some(text) = text {
                 return self.handle_event(Event::TextInput { text });
@@ -148,11 +201,21 @@ impl LevelEditor {
         if let Some(key) = key {
             self.handle_event(Event::KeyDown {
                 keycode: key.into(),
+                modifiers: modifiers.into(),
             })
         } else {
             false
         }
     }
+    pub fn tick_interval(&self) -> u32 {
+        TICK_INTERVAL_MS
+    }
+    pub fn needs_ticks(&self) -> bool {
+        self.state.needs_ticks(&self.context)
+    }
+    pub fn tick(&mut self, milliseconds: u32) -> bool {
+        self.handle_event(Event::Tick { milliseconds })
+    }
     pub fn add_level_file(&mut self, name: String, data: &[u8]) {
         self.context.level_lister.add_file(name, data);
     }
@@ -162,10 +225,36 @@ impl LevelEditor {
     }
 }
 
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy)]
+pub struct Modifiers {
+    shift: bool,
+    ctrl: bool,
+    alt: bool,
+}
+
+#[wasm_bindgen]
+impl Modifiers {
+    pub fn new(shift: bool, ctrl: bool, alt: bool) -> Self {
+        Self { shift, ctrl, alt }
+    }
+}
+
+impl From<Modifiers> for common::event::Modifiers {
+    fn from(modifiers: Modifiers) -> Self {
+        Self {
+            shift: modifiers.shift,
+            ctrl: modifiers.ctrl,
+            alt: modifiers.alt,
+        }
+    }
+}
+
 #[wasm_bindgen]
 #[derive(Debug)]
 pub enum MouseButton {
     Left,
+    Middle,
     Right,
 }
 
@@ -173,6 +262,7 @@ impl From<MouseButton> for common::event::MouseButton {
     fn from(button: MouseButton) -> Self {
         match button {
             MouseButton::Left => Self::Left,
+            MouseButton::Middle => Self::Middle,
             MouseButton::Right => Self::Right,
         }
     }
@@ -183,8 +273,13 @@ impl From<MouseButton> for common::event::MouseButton {
 pub enum Keycode {
     Escape,
     Backspace,
+    Tab,
     Return,
     Space,
+    Delete,
+    Insert,
+    Home,
+    End,
     PageDown,
     PageUp,
     Up,
@@ -197,23 +292,53 @@ pub enum Keycode {
     Minus,
     Plus,
     A,
+    B,
     C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
     Q,
+    R,
     S,
+    T,
+    U,
+    V,
     W,
     X,
     Y,
     Z,
+    Num0,
     Num1,
     Num2,
+    Num3,
+    Num4,
+    Num5,
+    Num6,
+    Num7,
+    Num8,
+    Num9,
     F1,
     F2,
     F3,
     F4,
+    F5,
     F6,
     F7,
     F8,
     F9,
+    F10,
+    F11,
+    F12,
 }
 
 impl From<Keycode> for common::event::Keycode {
@@ -221,8 +346,13 @@ impl From<Keycode> for common::event::Keycode {
         match key {
             Keycode::Escape => Self::Escape,
             Keycode::Backspace => Self::Backspace,
+            Keycode::Tab => Self::Tab,
             Keycode::Return => Self::Return,
             Keycode::Space => Self::Space,
+            Keycode::Delete => Self::Delete,
+            Keycode::Insert => Self::Insert,
+            Keycode::Home => Self::Home,
+            Keycode::End => Self::End,
             Keycode::PageDown => Self::PageDown,
             Keycode::PageUp => Self::PageUp,
             Keycode::Up => Self::Up,
@@ -235,23 +365,53 @@ impl From<Keycode> for common::event::Keycode {
             Keycode::Minus => Self::Minus,
             Keycode::Plus => Self::Plus,
             Keycode::A => Self::A,
+            Keycode::B => Self::B,
             Keycode::C => Self::C,
+            Keycode::D => Self::D,
+            Keycode::E => Self::E,
+            Keycode::F => Self::F,
+            Keycode::G => Self::G,
+            Keycode::H => Self::H,
+            Keycode::I => Self::I,
+            Keycode::J => Self::J,
+            Keycode::K => Self::K,
+            Keycode::L => Self::L,
+            Keycode::M => Self::M,
+            Keycode::N => Self::N,
+            Keycode::O => Self::O,
+            Keycode::P => Self::P,
             Keycode::Q => Self::Q,
+            Keycode::R => Self::R,
             Keycode::S => Self::S,
+            Keycode::T => Self::T,
+            Keycode::U => Self::U,
+            Keycode::V => Self::V,
             Keycode::W => Self::W,
             Keycode::X => Self::X,
             Keycode::Y => Self::Y,
             Keycode::Z => Self::Z,
+            Keycode::Num0 => Self::Num0,
             Keycode::Num1 => Self::Num1,
             Keycode::Num2 => Self::Num2,
+            Keycode::Num3 => Self::Num3,
+            Keycode::Num4 => Self::Num4,
+            Keycode::Num5 => Self::Num5,
+            Keycode::Num6 => Self::Num6,
+            Keycode::Num7 => Self::Num7,
+            Keycode::Num8 => Self::Num8,
+            Keycode::Num9 => Self::Num9,
             Keycode::F1 => Self::F1,
             Keycode::F2 => Self::F2,
             Keycode::F3 => Self::F3,
             Keycode::F4 => Self::F4,
+            Keycode::F5 => Self::F5,
             Keycode::F6 => Self::F6,
             Keycode::F7 => Self::F7,
             Keycode::F8 => Self::F8,
             Keycode::F9 => Self::F9,
+            Keycode::F10 => Self::F10,
+            Keycode::F11 => Self::F11,
+            Keycode::F12 => Self::F12,
         }
     }
 }
@@ -310,6 +470,18 @@ impl LevelLister for WebLevelLister {
     fn load_level(&self, index: usize) -> Vec<u8> {
         self.files[index].1.clone()
     }
+
+    fn load_sidecar(&self, index: usize, extension: &str) -> Option<Vec<u8>> {
+        let level_name = &self.files[index].0;
+        let stem = level_name
+            .rsplit_once('.')
+            .map_or(level_name.as_str(), |(stem, _)| stem);
+        let sidecar_name = format!("{}.{}", stem, extension);
+        self.files
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&sidecar_name))
+            .map(|(_, data)| data.clone())
+    }
 }
 
 #[wasm_bindgen(module = "/src/js/file.js")]
@@ -320,7 +492,14 @@ extern "C" {
 struct WebLevelWriter;
 
 impl LevelWriter for WebLevelWriter {
-    fn write(filename: &str, level_data: &[u8]) {
+    fn write(filename: &str, level_data: &[u8]) -> std::io::Result<()> {
         write_file(filename, level_data);
+        Ok(())
+    }
+
+    // Saved levels are downloads which the page cannot delete, and sidecars are only read when
+    // uploaded together with their level
+    fn remove(_filename: &str) -> std::io::Result<()> {
+        Ok(())
     }
 }